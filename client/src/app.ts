@@ -3,19 +3,23 @@ import {
   Keypair,
   PublicKey,
   SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
   SYSVAR_RENT_PUBKEY,
   Transaction,
   TransactionInstruction,
   LAMPORTS_PER_SOL,
   Struct,
 } from "@solana/web3.js";
+import BN from "bn.js";
 import { readFileSync } from "fs";
 import { fileURLToPath } from "url";
 import { dirname } from "path";
 import {
-  decodeDepositHistory,
-  DepositHistory,
+  ACCOUNT_HEADER_LEN,
+  decodeDonorRecord,
+  DonorRecord,
   encodeDepositIx,
+  encodeInitializeConfigIx,
   encodeInitializeIx,
   encodeWithdrawIx,
 } from "./serialization";
@@ -25,8 +29,27 @@ function delay(ms: number) {
 }
 
 export class App {
-  static DEPOSIT_HISTORY_SEED = "deposit-history-seed";
+  static DONOR_RECORD_SEED = "donor";
+  static ACCESS_LIST_SEED = "access";
+  static ALLOW_LIST = 0;
+  static DENY_LIST = 1;
   static DEPOSIT_SEED = "deposit";
+  static CONFIG_SEED = "config";
+  static CAMPAIGN_SEED = "campaign";
+  static FEE_VAULT_SEED = "fee-vault";
+  static RECEIPT_SEED = "receipt";
+  static RECEIPT_MINT_SEED = "receipt-mint";
+  static TOKEN_PROGRAM_ID = new PublicKey(
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+  );
+  static ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey(
+    "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
+  );
+  static BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+    "BPFLoaderUpgradeab1e11111111111111111111111"
+  );
+  static CAMPAIGN_ID = 1;
+  static CAMPAIGN_DURATION = 7 * 24 * 60 * 60;
 
   admin: Keypair;
   user: Keypair;
@@ -34,7 +57,9 @@ export class App {
 
   connection: Connection;
 
-  depositHistory: PublicKey;
+  config: PublicKey;
+  campaign: PublicKey;
+  donorRecord: PublicKey;
   deposit: PublicKey;
 
   constructor() {
@@ -52,21 +77,41 @@ export class App {
       "https://api.devnet.solana.com",
       "confirmed"
     );
-    this.depositHistory = new PublicKey(0);
+    this.config = new PublicKey(0);
+    this.campaign = new PublicKey(0);
+    this.donorRecord = new PublicKey(0);
     this.deposit = new PublicKey(0);
   }
 
   async init() {
-    this.depositHistory = (
+    this.config = (
       await PublicKey.findProgramAddress(
-        [Buffer.from(App.DEPOSIT_HISTORY_SEED, "utf-8")],
+        [Buffer.from(App.CONFIG_SEED, "utf-8")],
+        this.program.publicKey
+      )
+    )[0];
+
+    this.campaign = (
+      await PublicKey.findProgramAddress(
+        [Buffer.from(App.CAMPAIGN_SEED, "utf-8"), App.campaignSeed()],
+        this.program.publicKey
+      )
+    )[0];
+
+    this.donorRecord = (
+      await PublicKey.findProgramAddress(
+        [
+          Buffer.from(App.DONOR_RECORD_SEED, "utf-8"),
+          App.campaignSeed(),
+          this.user.publicKey.toBuffer(),
+        ],
         this.program.publicKey
       )
     )[0];
 
     this.deposit = (
       await PublicKey.findProgramAddress(
-        [Buffer.from(App.DEPOSIT_SEED, "utf-8")],
+        [Buffer.from(App.DEPOSIT_SEED, "utf-8"), App.campaignSeed()],
         this.program.publicKey
       )
     )[0];
@@ -77,10 +122,18 @@ export class App {
       process.exit(1);
     }
 
+    const config = await this.connection.getAccountInfo(this.config);
+    if (!config) {
+      console.log(
+        "Config was not initialised. Sending initialize config transaction."
+      );
+      await this.initializeConfigIx();
+    }
+
     const account = await this.connection.getAccountInfo(this.deposit);
     if (!account) {
       console.log(
-        "Program was not initialised. Sending initialize transaction."
+        "Campaign was not initialised. Sending initialize transaction."
       );
       await this.initializeIx();
     }
@@ -88,10 +141,44 @@ export class App {
     console.log("program", this.program.publicKey.toBase58());
     console.log("admin", this.admin.publicKey.toBase58());
     console.log("user", this.user.publicKey.toBase58());
-    console.log("deposit history", this.depositHistory.toBase58());
+    console.log("config", this.config.toBase58());
+    console.log("campaign", this.campaign.toBase58());
+    console.log("donor record", this.donorRecord.toBase58());
     console.log("deposit", this.deposit.toBase58());
   }
 
+  private async initializeConfigIx() {
+    const programData = (
+      await PublicKey.findProgramAddress(
+        [this.program.publicKey.toBuffer()],
+        App.BPF_LOADER_UPGRADEABLE_ID
+      )
+    )[0];
+
+    const initializeConfigIx = new TransactionInstruction({
+      programId: this.program.publicKey,
+      keys: [
+        {
+          pubkey: this.admin.publicKey,
+          isSigner: true,
+          isWritable: true,
+        },
+        { pubkey: this.config, isSigner: false, isWritable: true },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: programData, isSigner: false, isWritable: false },
+      ],
+      data: encodeInitializeConfigIx(0.01 * LAMPORTS_PER_SOL),
+    });
+
+    const tx = new Transaction().add(initializeConfigIx);
+    const txHash = await this.connection.sendTransaction(tx, [this.admin], {
+      preflightCommitment: "max",
+    });
+    console.log("initialize config tx", txHash);
+    await delay(3000);
+  }
+
   private async initializeIx() {
     const initializeIx = new TransactionInstruction({
       programId: this.program.publicKey,
@@ -101,12 +188,18 @@ export class App {
           isSigner: true,
           isWritable: true,
         },
-        { pubkey: this.depositHistory, isSigner: false, isWritable: true },
+        { pubkey: this.config, isSigner: false, isWritable: false },
+        { pubkey: this.campaign, isSigner: false, isWritable: true },
         { pubkey: this.deposit, isSigner: false, isWritable: true },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
-      data: encodeInitializeIx(),
+      data: encodeInitializeIx(
+        App.CAMPAIGN_ID,
+        0.1 * LAMPORTS_PER_SOL,
+        Math.floor(Date.now() / 1000) + App.CAMPAIGN_DURATION
+      ),
     });
 
     const tx = new Transaction().add(initializeIx);
@@ -124,7 +217,9 @@ export class App {
       process.exit(1);
     }
     const rent_lamports =
-      await this.connection.getMinimumBalanceForRentExemption(0);
+      await this.connection.getMinimumBalanceForRentExemption(
+        ACCOUNT_HEADER_LEN
+      );
     return (account.lamports - rent_lamports) / LAMPORTS_PER_SOL;
   }
 
@@ -139,17 +234,59 @@ export class App {
     return (account.lamports - rent_lamports) / LAMPORTS_PER_SOL;
   }
 
-  async getDepositHistory(): Promise<DepositHistory> {
-    const account = await this.connection.getAccountInfo(this.depositHistory);
+  async getDonorRecord(): Promise<DonorRecord | null> {
+    const account = await this.connection.getAccountInfo(this.donorRecord);
     if (!account) {
-      console.error("deposit account is not found");
-      process.exit(1);
+      return null;
     }
-    return decodeDepositHistory(account.data);
+    return decodeDonorRecord(account.data);
+  }
+
+  private async accessListEntry(list: number): Promise<PublicKey> {
+    return (
+      await PublicKey.findProgramAddress(
+        [
+          Buffer.from(App.ACCESS_LIST_SEED, "utf-8"),
+          Buffer.from([list]),
+          this.user.publicKey.toBuffer(),
+        ],
+        this.program.publicKey
+      )
+    )[0];
   }
 
   async depositSol(amount: number): Promise<void> {
     const depositLamports = amount * LAMPORTS_PER_SOL;
+    const allowEntry = await this.accessListEntry(App.ALLOW_LIST);
+    const denyEntry = await this.accessListEntry(App.DENY_LIST);
+    const feeVault = (
+      await PublicKey.findProgramAddress(
+        [Buffer.from(App.FEE_VAULT_SEED, "utf-8")],
+        this.program.publicKey
+      )
+    )[0];
+    const receiptConfig = (
+      await PublicKey.findProgramAddress(
+        [Buffer.from(App.RECEIPT_SEED, "utf-8")],
+        this.program.publicKey
+      )
+    )[0];
+    const receiptMint = (
+      await PublicKey.findProgramAddress(
+        [Buffer.from(App.RECEIPT_MINT_SEED, "utf-8")],
+        this.program.publicKey
+      )
+    )[0];
+    const receiptAccount = (
+      await PublicKey.findProgramAddress(
+        [
+          this.user.publicKey.toBuffer(),
+          App.TOKEN_PROGRAM_ID.toBuffer(),
+          receiptMint.toBuffer(),
+        ],
+        App.ASSOCIATED_TOKEN_PROGRAM_ID
+      )
+    )[0];
     const depositIx = new TransactionInstruction({
       programId: this.program.publicKey,
       keys: [
@@ -158,11 +295,27 @@ export class App {
           isSigner: true,
           isWritable: true,
         },
+        { pubkey: this.config, isSigner: false, isWritable: false },
+        { pubkey: this.campaign, isSigner: false, isWritable: true },
         { pubkey: this.deposit, isSigner: false, isWritable: true },
-        { pubkey: this.depositHistory, isSigner: false, isWritable: true },
+        { pubkey: this.donorRecord, isSigner: false, isWritable: true },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: allowEntry, isSigner: false, isWritable: false },
+        { pubkey: denyEntry, isSigner: false, isWritable: false },
+        { pubkey: feeVault, isSigner: false, isWritable: true },
+        { pubkey: receiptConfig, isSigner: false, isWritable: false },
+        { pubkey: receiptMint, isSigner: false, isWritable: true },
+        { pubkey: receiptAccount, isSigner: false, isWritable: true },
+        { pubkey: App.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        {
+          pubkey: App.ASSOCIATED_TOKEN_PROGRAM_ID,
+          isSigner: false,
+          isWritable: false,
+        },
       ],
-      data: encodeDepositIx(depositLamports),
+      data: encodeDepositIx(App.CAMPAIGN_ID, depositLamports),
     });
 
     const tx = new Transaction().add(depositIx);
@@ -173,29 +326,37 @@ export class App {
     await delay(3000);
   }
 
-  async withdrawSol(): Promise<void> {
+  async withdrawSol(amount: number, destination: PublicKey): Promise<void> {
+    const withdrawLamports = amount * LAMPORTS_PER_SOL;
     const withdrawIx = new TransactionInstruction({
       programId: this.program.publicKey,
       keys: [
         {
           pubkey: this.admin.publicKey,
           isSigner: true,
-          isWritable: true,
+          isWritable: false,
         },
+        { pubkey: this.config, isSigner: false, isWritable: false },
+        { pubkey: this.campaign, isSigner: false, isWritable: false },
         { pubkey: this.deposit, isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: true },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
       ],
-      data: encodeWithdrawIx(),
+      data: encodeWithdrawIx(App.CAMPAIGN_ID, withdrawLamports),
     });
 
     const tx = new Transaction().add(withdrawIx);
     const txHash = await this.connection.sendTransaction(tx, [this.admin], {
       preflightCommitment: "max",
     });
-    console.log(`Withdraw all SOL tx`, txHash);
+    console.log(`withdraw ${amount} SOL tx`, txHash);
     await delay(3000);
   }
 
+  static campaignSeed(): Buffer {
+    return new BN(App.CAMPAIGN_ID).toArrayLike(Buffer, "le", 8);
+  }
+
   static readKeypairFromPath(path: string): Keypair {
     const data = JSON.parse(readFileSync(path, "utf-8"));
     return Keypair.fromSecretKey(Buffer.from(data));
@@ -3,8 +3,8 @@ import { App } from "./app";
 const app = new App();
 await app.init();
 
-const history1 = await app.getDepositHistory();
-console.log("deposit history", history1.history);
+const record1 = await app.getDonorRecord();
+console.log("donor record", record1?.amount ?? 0);
 
 console.log("deposit balance", await app.getDepositedSol());
 
@@ -13,9 +13,9 @@ await app.depositSol(0.2);
 console.log("deposit balance", await app.getDepositedSol());
 
 console.log("admin balance:", await app.getAdminSol());
-await app.withdrawSol();
+await app.withdrawSol(0.1, app.admin.publicKey);
 console.log("deposit", await app.getDepositedSol());
 console.log("admin balance:", await app.getAdminSol());
 
-const history2 = await app.getDepositHistory();
-console.log("deposit history", history2.history);
+const record2 = await app.getDonorRecord();
+console.log("donor record", record2?.amount ?? 0);
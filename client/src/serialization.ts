@@ -8,6 +8,51 @@ enum Instructions {
   Deposit = 0,
   Withdraw = 1,
   Initialize = 2,
+  ProposeAdmin = 3,
+  AcceptAdmin = 4,
+  CancelAdminProposal = 5,
+  SetMultisig = 6,
+  CreateWithdrawProposal = 7,
+  ApproveProposal = 8,
+  RejectProposal = 9,
+  ExecuteProposal = 10,
+  SetTimelock = 11,
+  CancelProposal = 12,
+  Pause = 13,
+  Unpause = 14,
+  Refund = 15,
+  SetRefundsEnabled = 16,
+  ClaimRefund = 17,
+  InitializeConfig = 18,
+  Reclaim = 19,
+  InitializeToken = 20,
+  DepositToken = 21,
+  WithdrawToken = 22,
+  MigrateHistory = 23,
+  MigrateAccount = 24,
+  Close = 25,
+  SetDepositLimits = 26,
+  AddToAccessList = 27,
+  RemoveFromAccessList = 28,
+  SetAllowlistRequired = 29,
+  CreateSubscription = 30,
+  CollectSubscription = 31,
+  CreateStream = 32,
+  ClaimStream = 33,
+  CancelStream = 34,
+  SetPayoutTable = 35,
+  Distribute = 36,
+  SetProtocolFee = 37,
+  CollectFees = 38,
+  InitializeReceipts = 39,
+  SetReceiptRate = 40,
+  AdoptLegacyDeposit = 41,
+  ReclaimToken = 42,
+  CloseDonorRecords = 43,
+  CloseToken = 44,
+  CancelSubscription = 45,
+  ApplyMultisig = 46,
+  CreateTokenWithdrawProposal = 47,
 }
 
 export class DepositHistory extends Struct {
@@ -36,8 +81,78 @@ export function decodeDepositHistory(data: Buffer): DepositHistory {
   return borsh.deserializeUnchecked(depositHistorySchema, DepositHistory, data);
 }
 
-export function encodeDepositIx(amount: number): Buffer {
-  const value = new Struct({ id: Instructions.Deposit, amount: amount });
+export class DonorRecord extends Struct {
+  get donor(): PublicKey {
+    // @ts-ignore
+    return new PublicKey(this._donor);
+  }
+
+  get amount(): number {
+    // @ts-ignore
+    return this._amount.toNumber() / LAMPORTS_PER_SOL;
+  }
+
+  get grossAmount(): number {
+    // @ts-ignore
+    return this._grossAmount.toNumber() / LAMPORTS_PER_SOL;
+  }
+}
+
+const donorRecordSchema = new Map([
+  [
+    DonorRecord,
+    {
+      kind: "struct",
+      fields: [
+        ["_donor", ["u8", 32]],
+        ["_amount", "u64"],
+        ["_receipts", "u64"],
+        ["_grossAmount", "u64"],
+      ],
+    },
+  ],
+]);
+
+// Program accounts start with an 8-byte discriminator and a version byte
+export const ACCOUNT_HEADER_LEN = 9;
+
+export function decodeDonorRecord(data: Buffer): DonorRecord {
+  return borsh.deserializeUnchecked(
+    donorRecordSchema,
+    DonorRecord,
+    data.subarray(ACCOUNT_HEADER_LEN)
+  );
+}
+
+export function encodeDepositIx(campaignId: number, amount: number): Buffer {
+  const value = new Struct({
+    id: Instructions.Deposit,
+    campaignId: campaignId,
+    amount: amount,
+  });
+  const schema = new Map([
+    [
+      Struct,
+      {
+        kind: "struct",
+        fields: [
+          ["id", "u8"],
+          ["campaignId", "u64"],
+          ["amount", "u64"],
+        ],
+      },
+    ],
+  ]);
+
+  return Buffer.from(borsh.serialize(schema, value));
+}
+
+export function encodeWithdrawIx(campaignId: number, amount: number): Buffer {
+  const value = new Struct({
+    id: Instructions.Withdraw,
+    campaignId: campaignId,
+    amount: amount,
+  });
   const schema = new Map([
     [
       Struct,
@@ -45,6 +160,7 @@ export function encodeDepositIx(amount: number): Buffer {
         kind: "struct",
         fields: [
           ["id", "u8"],
+          ["campaignId", "u64"],
           ["amount", "u64"],
         ],
       },
@@ -54,8 +170,79 @@ export function encodeDepositIx(amount: number): Buffer {
   return Buffer.from(borsh.serialize(schema, value));
 }
 
-export function encodeWithdrawIx(): Buffer {
-  const value = new Struct({ id: Instructions.Withdraw });
+export function encodeInitializeIx(
+  campaignId: number,
+  goalLamports: number,
+  deadline: number
+): Buffer {
+  const value = new Struct({
+    id: Instructions.Initialize,
+    campaignId: campaignId,
+    goalLamports: goalLamports,
+    deadline: deadline,
+  });
+  const schema = new Map([
+    [
+      Struct,
+      {
+        kind: "struct",
+        fields: [
+          ["id", "u8"],
+          ["campaignId", "u64"],
+          ["goalLamports", "u64"],
+          ["deadline", "u64"],
+        ],
+      },
+    ],
+  ]);
+
+  return Buffer.from(borsh.serialize(schema, value));
+}
+
+export function encodeInitializeConfigIx(withdrawThreshold: number): Buffer {
+  const value = new Struct({
+    id: Instructions.InitializeConfig,
+    withdrawThreshold: withdrawThreshold,
+  });
+  const schema = new Map([
+    [
+      Struct,
+      {
+        kind: "struct",
+        fields: [
+          ["id", "u8"],
+          ["withdrawThreshold", "u64"],
+        ],
+      },
+    ],
+  ]);
+
+  return Buffer.from(borsh.serialize(schema, value));
+}
+
+export function encodeProposeAdminIx(newAdmin: PublicKey): Buffer {
+  const value = new Struct({
+    id: Instructions.ProposeAdmin,
+    newAdmin: newAdmin.toBytes(),
+  });
+  const schema = new Map([
+    [
+      Struct,
+      {
+        kind: "struct",
+        fields: [
+          ["id", "u8"],
+          ["newAdmin", [32]],
+        ],
+      },
+    ],
+  ]);
+
+  return Buffer.from(borsh.serialize(schema, value));
+}
+
+export function encodeAcceptAdminIx(): Buffer {
+  const value = new Struct({ id: Instructions.AcceptAdmin });
   const schema = new Map([
     [
       Struct,
@@ -69,8 +256,8 @@ export function encodeWithdrawIx(): Buffer {
   return Buffer.from(borsh.serialize(schema, value));
 }
 
-export function encodeInitializeIx(): Buffer {
-  const value = new Struct({ id: Instructions.Initialize });
+export function encodeCancelAdminProposalIx(): Buffer {
+  const value = new Struct({ id: Instructions.CancelAdminProposal });
   const schema = new Map([
     [
       Struct,
@@ -83,3 +270,29 @@ export function encodeInitializeIx(): Buffer {
 
   return Buffer.from(borsh.serialize(schema, value));
 }
+
+export function encodeSetMultisigIx(
+  signers: PublicKey[],
+  threshold: number
+): Buffer {
+  const value = new Struct({
+    id: Instructions.SetMultisig,
+    signers: signers.map((signer) => signer.toBytes()),
+    threshold: threshold,
+  });
+  const schema = new Map([
+    [
+      Struct,
+      {
+        kind: "struct",
+        fields: [
+          ["id", "u8"],
+          ["signers", [[32]]],
+          ["threshold", "u8"],
+        ],
+      },
+    ],
+  ]);
+
+  return Buffer.from(borsh.serialize(schema, value));
+}
@@ -6,6 +6,102 @@ use thiserror::Error;
 pub enum DonationError {
     #[error("Admin signature is required")]
     AdminRequired,
+    #[error("Withdraw amount exceeds the withdrawable balance")]
+    InsufficientWithdrawableBalance,
+    #[error("There is no pending admin proposal")]
+    NoPendingAdmin,
+    #[error("Pending admin signature is required")]
+    PendingAdminRequired,
+    #[error("Not enough multisig signers")]
+    NotEnoughSigners,
+    #[error("Invalid multisig signers or threshold")]
+    InvalidMultisig,
+    #[error("Approver signature is required")]
+    ApproverRequired,
+    #[error("Proposal is not active")]
+    ProposalNotActive,
+    #[error("Approver has already voted on the proposal")]
+    AlreadyVoted,
+    #[error("Proposal does not have enough approvals")]
+    NotEnoughApprovals,
+    #[error("Memo is too long")]
+    MemoTooLong,
+    #[error("Timelock has not expired yet")]
+    TimelockNotExpired,
+    #[error("Withdrawals have to be queued with a proposal while the timelock is enabled")]
+    TimelockEnabled,
+    #[error("Timelock delay can't be decreased or exceed 30 days")]
+    InvalidTimelock,
+    #[error("Program is paused")]
+    Paused,
+    #[error("Refund amount exceeds the donor's deposit")]
+    RefundExceedsDeposit,
+    #[error("Refunds are disabled")]
+    RefundsDisabled,
+    #[error("Campaign deadline must be in the future")]
+    InvalidDeadline,
+    #[error("Campaign deadline has passed")]
+    CampaignEnded,
+    #[error("Campaign goal is not reached")]
+    GoalNotReached,
+    #[error("Campaign has not failed")]
+    CampaignNotFailed,
+    #[error("Nothing to reclaim")]
+    NothingToReclaim,
+    #[error("Token account is not the campaign vault")]
+    InvalidVault,
+    #[error("Migration batch size is invalid")]
+    InvalidBatch,
+    #[error("Donor is not in the legacy history")]
+    DonorNotInHistory,
+    #[error("Account has a wrong type")]
+    InvalidAccountDiscriminator,
+    #[error("Account version is not supported, upgrade it with MigrateAccount")]
+    UnsupportedAccountVersion,
+    #[error("Account is closed")]
+    AccountClosed,
+    #[error("Deposit account still holds donations")]
+    DepositNotEmpty,
+    #[error("Minimum deposit exceeds a maximum deposit limit")]
+    InvalidDepositLimits,
+    #[error("Deposit amount is below the minimum deposit")]
+    DepositTooSmall,
+    #[error("Deposit amount exceeds the maximum deposit per transaction")]
+    DepositTooLarge,
+    #[error("Deposit exceeds the maximum total deposit per donor")]
+    DonorLimitExceeded,
+    #[error("Donor is not on the allowlist")]
+    DonorNotAllowed,
+    #[error("Donor is on the denylist")]
+    DonorDenied,
+    #[error("Subscription amount and interval must be positive, the interval at most 365 days and the allowance must fit in u64")]
+    InvalidSubscription,
+    #[error("Subscription is not due yet")]
+    SubscriptionNotDue,
+    #[error(
+        "Stream rate must be positive, the end after the start and the start after the timelock"
+    )]
+    InvalidStream,
+    #[error("Nothing accrued since the last claim")]
+    NothingAccrued,
+    #[error("Payout shares must be positive, distinct and sum to 10000 basis points")]
+    InvalidPayoutTable,
+    #[error("Protocol fee must not exceed 10000 basis points")]
+    InvalidFee,
+    #[error("Receipt amount overflows, the receipt rate is too high")]
+    ReceiptAmountOverflow,
+    #[error("Legacy deposit was already adopted by a campaign")]
+    LegacyDepositAdopted,
+    #[error("Legacy history is migrated only into the campaign that adopted the legacy deposit")]
+    NotLegacyCampaign,
+    #[error("Campaign still has donor records or token vaults, close them first")]
+    CampaignHasDonors,
+    #[error("Donor's token account already has a delegate, revoke it before subscribing")]
+    SourceHasDelegate,
+    #[error("There is no pending multisig change")]
+    NoPendingMultisig,
+    #[error("Campaign still has active withdraw proposals, execute or cancel them first")]
+    CampaignHasProposals,
 }
 
 impl From<DonationError> for ProgramError {
@@ -2,102 +2,2193 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
 use solana_program::{system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
 
-use crate::state::DepositHistoryData;
-use crate::{id, ADMIN_PUBKEY};
+use crate::id;
+use crate::state::{
+    AccessList, AccessListEntry, AccountKind, Campaign, Config, DepositHistoryData, DepositTotals,
+    DonorRecord, FeeVault, PayoutShare, PayoutTable, ReceiptConfig, Stream, Subscription,
+    WithdrawProposal,
+};
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub enum DepositInstructions {
-    /// Deposit lamports to the deposit account
+    /// Deposit lamports to the deposit account of the campaign. Fails after the deadline
+    /// and outside the deposit limits.
+    /// The donor record is created on the first deposit, the donor pays its rent.
+    /// The protocol fee goes to the fee vault, the donor record is credited with
+    /// the net amount that reached the deposit account and keeps the gross amount
+    /// as history. Receipt tokens are minted to the donor once receipts are
+    /// initialized, the donor pays for the receipt token account
     ///
     /// Accounts expected:
     /// 0. `[signer, writable]` The account of the person who wants to send the donation
-    /// 1. `[writable]` The deposit accumulate account
-    /// 2. `[writable]` The PDA account for storing data
-    /// 3. `[]` System program
-    Deposit { amount: u64 },
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The donor record PDA account
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    /// 8. `[]` The donor's allowlist entry PDA account
+    /// 9. `[]` The donor's denylist entry PDA account
+    /// 10. `[writable]` The fee vault PDA account
+    /// 11. `[]` The receipt config PDA account
+    /// 12. `[writable]` The receipt mint PDA account
+    /// 13. `[writable]` The donor's receipt associated token account
+    /// 14. `[]` SPL Token program
+    /// 15. `[]` Associated token account program
+    Deposit { campaign_id: u64, amount: u64 },
+
+    /// Send `amount` lamports deposited to the campaign to the destination account
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The destination account
+    /// 5. `[]` Rent sysvar
+    /// 6. ..6+M `[signer]` Multisig signers, when multisig is enabled
+    ///
+    /// Fails until the campaign goal is reached and while the timelock is enabled,
+    /// use `CreateWithdrawProposal` instead
+    Withdraw { campaign_id: u64, amount: u64 },
+
+    /// Create campaign and deposit accounts of the campaign
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` Clock sysvar
+    /// 6. `[]` System program
+    /// 7. ..7+M `[signer]` Multisig signers, when multisig is enabled
+    Initialize {
+        campaign_id: u64,
+        goal_lamports: u64,
+        deadline: i64,
+    },
+
+    /// Nominate a new admin. The nominee has to accept it with `AcceptAdmin`
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. `[]` Clock sysvar
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    ProposeAdmin { new_admin: Pubkey },
+
+    /// Become the admin nominated by `ProposeAdmin` once the timelock has expired
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The pending admin account
+    /// 1. `[writable]` The config account
+    /// 2. `[]` Clock sysvar
+    AcceptAdmin,
+
+    /// Drop the pending admin nomination
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    CancelAdminProposal,
+
+    /// Require `threshold` of `signers` to authorize admin instructions.
+    /// Empty `signers` switches back to the single admin key.
+    /// While the timelock is enabled the change is queued for `ApplyMultisig`,
+    /// replacing any change queued before
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. `[]` Clock sysvar
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    SetMultisig { signers: Vec<Pubkey>, threshold: u8 },
+
+    /// Create a proposal to send `amount` lamports deposited to the campaign
+    /// to `destination`. The proposer's approval is recorded right away
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The approver account
+    /// 1. `[writable]` The config account
+    /// 2. `[writable]` The proposal account for the next proposal id
+    /// 3. `[]` Rent sysvar
+    /// 4. `[]` Clock sysvar
+    /// 5. `[]` System program
+    /// 6. `[writable]` The campaign account
+    CreateWithdrawProposal {
+        campaign_id: u64,
+        amount: u64,
+        destination: Pubkey,
+        memo: String,
+    },
+
+    /// Approve an active withdraw proposal. The proposal is queued once it
+    /// reaches the threshold
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The approver account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The proposal account
+    /// 3. `[]` Clock sysvar
+    ApproveProposal { proposal_id: u64 },
+
+    /// Reject an active withdraw proposal. The proposal is closed once it
+    /// can't reach the threshold anymore
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The approver account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The proposal account
+    /// 3. `[]` Clock sysvar
+    /// 4. `[writable]` The campaign account of the proposal
+    RejectProposal { proposal_id: u64 },
+
+    /// Send the proposed lamports or tokens once the proposal has enough approvals
+    /// and the timelock has expired
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The config account
+    /// 1. `[writable]` The campaign account
+    /// 2. `[writable]` The proposal account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The destination account, a token account for token proposals
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` The mint account, for token proposals
+    /// 8. `[writable]` The vault, for token proposals
+    /// 9. `[]` The token totals PDA account, for token proposals
+    /// 10. `[]` Token program owning the mint, for token proposals
+    ExecuteProposal { proposal_id: u64 },
+
+    /// Set the delay in seconds between queuing a withdrawal or admin change
+    /// and executing it. The delay can only be increased, up to 30 days
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    SetTimelock { delay: i64 },
+
+    /// Cancel an active withdraw proposal
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The proposal account
+    /// 3. `[writable]` The campaign account of the proposal
+    /// 4. ..4+M `[signer]` Multisig signers, when multisig is enabled
+    CancelProposal { proposal_id: u64 },
+
+    /// Reject deposits and withdrawals until `Unpause`
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    Pause,
+
+    /// Accept deposits and withdrawals again
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    Unpause,
+
+    /// Send `amount` lamports back to the donor and decrease the donor record.
+    /// The receipts minted for the refunded share are burned when the donor signs too
+    /// and holds them, otherwise they are recorded as unburned in the donor record
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The donor record PDA account
+    /// 5. `[writable]` The donor account, `[signer]` to burn the receipts
+    /// 6. `[]` Rent sysvar
+    /// 7. `[writable]` The receipt mint PDA account
+    /// 8. `[writable]` The donor's receipt token account
+    /// 9. `[]` SPL Token program
+    /// 10. ..10+M `[signer]` Multisig signers, when multisig is enabled
+    Refund {
+        campaign_id: u64,
+        donor: Pubkey,
+        amount: u64,
+    },
 
-    /// Send all deposited lamports to admin account
+    /// Allow or forbid donors to claim refunds themselves
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    SetRefundsEnabled { enabled: bool },
+
+    /// Take `amount` deposited lamports back while refunds are enabled, burning
+    /// the receipts minted for them
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The donor account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The donor record PDA account
+    /// 5. `[]` Rent sysvar
+    /// 6. `[writable]` The receipt mint PDA account
+    /// 7. `[writable]` The donor's receipt token account
+    /// 8. `[]` SPL Token program
+    ClaimRefund { campaign_id: u64, amount: u64 },
+
+    /// Create the config account. The signer becomes the admin and has to be
+    /// the upgrade authority of the program
     ///
     /// Accounts expected:
     /// 0. `[signer, writable]` The admin account
-    /// 1. `[writable]` The deposit accumulate account
+    /// 1. `[writable]` The config account
     /// 2. `[]` Rent sysvar
-    Withdraw,
+    /// 3. `[]` System program
+    /// 4. `[]` The program data account of the program
+    InitializeConfig { withdraw_threshold: u64 },
+
+    /// Take the whole deposited amount back after the campaign has failed,
+    /// i.e. the deadline has passed without reaching the goal. The receipts minted
+    /// for the deposits are burned, those the donor doesn't hold anymore are recorded
+    /// as unburned in the donor record
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The donor account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The donor record PDA account
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[writable]` The receipt mint PDA account
+    /// 8. `[writable]` The donor's receipt token account
+    /// 9. `[]` SPL Token program
+    Reclaim { campaign_id: u64 },
 
-    /// Create PDA and deposit accounts
+    /// Create the vault and the token totals accounts of the campaign for one mint.
+    /// Each mint has its own goal, tokens are withdrawn once it is reached and
+    /// reclaimed if it isn't reached by the deadline
     ///
     /// Accounts expected:
     /// 0. `[signer, writable]` The admin account
-    /// 1. `[writable]` The PDA account for storing data
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[]` The deposit accumulate account, owner of the vault
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The vault, associated token account of the deposit account
+    /// 6. `[writable]` The token totals PDA account
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` System program
+    /// 9. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 10. `[]` Associated token account program
+    /// 11. ..11+M `[signer]` Multisig signers, when multisig is enabled
+    InitializeToken { campaign_id: u64, goal_amount: u64 },
+
+    /// Deposit tokens to the vault of the campaign. Fails after the deadline.
+    /// Works with SPL Token and Token-2022 mints, the donor record is credited with
+    /// the amount the vault actually received after transfer fees
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account of the person who wants to send the donation
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The donor's token account
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The vault
+    /// 6. `[writable]` The token totals PDA account
+    /// 7. `[writable]` The token donor record PDA account
+    /// 8. `[]` Rent sysvar
+    /// 9. `[]` Clock sysvar
+    /// 10. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 11. `[]` System program
+    /// 12. `[]` The donor's allowlist entry PDA account
+    /// 13. `[]` The donor's denylist entry PDA account
+    DepositToken { campaign_id: u64, amount: u64 },
+
+    /// Send `amount` tokens deposited to the campaign to the destination token account.
+    /// Like lamports, tokens are released only after the goal of their mint is reached
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[]` The deposit accumulate account, owner of the vault
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The vault
+    /// 6. `[writable]` The destination token account
+    /// 7. `[]` The token totals PDA account
+    /// 8. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 9. ..9+M `[signer]` Multisig signers, when multisig is enabled
+    ///
+    /// Fails while the timelock is enabled, use `CreateTokenWithdrawProposal` instead
+    WithdrawToken { campaign_id: u64, amount: u64 },
+
+    /// Move `batch` entries of the legacy deposit history map into donor records of
+    /// the campaign that adopted the legacy deposit, the migrated amounts count
+    /// towards its goal. Anyone can send it, the payer covers the rent of new records.
+    /// Migrated entries are removed from the map, so the migration can be resumed
+    /// at any point. The history account is closed after the last entry and its rent
+    /// goes to the payer
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The payer account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The legacy history PDA account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` System program
+    /// 6. ..6+2*batch `[]` The donor account followed by its `[writable]` donor record account,
+    ///    for each of up to 8 donors
+    MigrateHistory { campaign_id: u64, batch: u8 },
+
+    /// Upgrade a program-owned account written by an older program version to the
+    /// current layout in place. Accounts created before the discriminator and version
+    /// header was introduced get the header prepended. Anyone can send it,
+    /// the payer covers the rent of the grown account
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The payer account
+    /// 1. `[writable]` The account at the address derived from `kind`
     /// 2. `[]` Rent sysvar
     /// 3. `[]` System program
-    Initialize,
+    MigrateAccount { kind: AccountKind },
+
+    /// Decommission the campaign. The deposit account must not hold anything but its rent,
+    /// the donor records and token vaults have to be closed and the withdraw proposals
+    /// executed, rejected or cancelled before, so nothing is left behind for a campaign
+    /// initialized later with the same id. The stream and the payout table are closed
+    /// with the campaign. The rent of the closed accounts goes to the recipient
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The recipient account
+    /// 5. `[]` Rent sysvar
+    /// 6. `[writable]` The stream PDA account
+    /// 7. `[writable]` The payout table PDA account
+    /// 8. ..8+M `[signer]` Multisig signers, when multisig is enabled
+    Close { campaign_id: u64 },
+
+    /// Set the lamports deposit limits on the amounts before protocol fees, zero
+    /// maximums mean no limit. Zero deposits are always rejected
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. ..2+M `[signer]` Multisig signers, when multisig is enabled
+    SetDepositLimits {
+        min_deposit: u64,
+        max_deposit_per_tx: u64,
+        max_total_per_donor: u64,
+    },
+
+    /// Put the donor on the allowlist or the denylist
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The access list entry PDA account
+    /// 3. `[]` Rent sysvar
+    /// 4. `[]` System program
+    /// 5. ..5+M `[signer]` Multisig signers, when multisig is enabled
+    AddToAccessList { list: AccessList, donor: Pubkey },
+
+    /// Take the donor off the allowlist or the denylist, the entry rent goes to the admin
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The access list entry PDA account
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    RemoveFromAccessList { list: AccessList, donor: Pubkey },
+
+    /// Choose whether only donors on the allowlist may deposit to the campaign.
+    /// Donors on the denylist are rejected either way
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    SetAllowlistRequired { campaign_id: u64, required: bool },
+
+    /// Set up a recurring token donation. The subscription PDA becomes the delegate
+    /// of the donor's token account for the payments due until the campaign deadline,
+    /// `CancelSubscription` stops it. Fails when the token account already has a delegate.
+    /// The interval is at most 365 days. The first collection is due immediately
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The donor account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The donor's token account
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The subscription PDA account
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` Clock sysvar
+    /// 8. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 9. `[]` System program
+    CreateSubscription {
+        campaign_id: u64,
+        amount: u64,
+        interval: i64,
+    },
+
+    /// Pull the due amount of a subscription into the vault. Anybody can call it,
+    /// the caller pays the rent of the token donor record when it is created
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The caller account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The subscription PDA account
+    /// 4. `[writable]` The donor's token account
+    /// 5. `[]` The mint account
+    /// 6. `[writable]` The vault
+    /// 7. `[writable]` The token totals PDA account
+    /// 8. `[writable]` The token donor record PDA account
+    /// 9. `[]` Rent sysvar
+    /// 10. `[]` Clock sysvar
+    /// 11. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 12. `[]` System program
+    /// 13. `[]` The donor's allowlist entry PDA account
+    /// 14. `[]` The donor's denylist entry PDA account
+    CollectSubscription { campaign_id: u64, donor: Pubkey },
+
+    /// Pay the beneficiary `rate` lamports per second from the deposit account,
+    /// starting at `start` and until `end` if set. One stream per campaign.
+    /// While the timelock is enabled `start` has to be at least the delay away
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The stream PDA account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` System program
+    /// 6. `[]` Clock sysvar
+    /// 7. ..7+M `[signer]` Multisig signers, when multisig is enabled
+    CreateStream {
+        campaign_id: u64,
+        beneficiary: Pubkey,
+        rate: u64,
+        start: i64,
+        end: Option<i64>,
+    },
+
+    /// Transfer the lamports accrued since the last claim to the beneficiary.
+    /// Anybody can call it. Fails while the goal is not reached, pays out only
+    /// what the deposit account holds and keeps the rest claimable
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The config account
+    /// 1. `[]` The campaign account
+    /// 2. `[writable]` The stream PDA account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[writable]` The beneficiary account
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    ClaimStream { campaign_id: u64 },
+
+    /// Stop the stream, the stream rent goes to the admin.
+    /// Lamports accrued but not claimed yet are not paid out
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The stream PDA account
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    CancelStream { campaign_id: u64 },
+
+    /// Set the recipients `Distribute` splits the withdrawable balance between.
+    /// Shares are in basis points and must sum to 10000.
+    /// `Distribute` waits for the timelock delay after every change
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The payout table PDA account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` System program
+    /// 6. `[]` Clock sysvar
+    /// 7. ..7+M `[signer]` Multisig signers, when multisig is enabled
+    SetPayoutTable {
+        campaign_id: u64,
+        shares: Vec<PayoutShare>,
+    },
+
+    /// Split the whole withdrawable balance of the deposit account between the
+    /// payout table recipients. Anybody can call it once the goal is reached
+    /// and the timelock has passed since the payout table was last set
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The config account
+    /// 1. `[]` The campaign account
+    /// 2. `[]` The payout table PDA account
+    /// 3. `[writable]` The deposit accumulate account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` Clock sysvar
+    /// 6. ..6+N `[writable]` The recipients, in payout table order
+    Distribute { campaign_id: u64 },
+
+    /// Take `basis_points` of every lamports deposit as the protocol fee, zero disables it.
+    /// Creates the fee vault on the first call
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. `[writable]` The fee vault PDA account
+    /// 3. `[]` Rent sysvar
+    /// 4. `[]` System program
+    /// 5. ..5+M `[signer]` Multisig signers, when multisig is enabled
+    SetProtocolFee {
+        basis_points: u16,
+        recipient: Pubkey,
+    },
+
+    /// Pay the fee vault balance above rent to the fee recipient. Anybody can call it
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The config account
+    /// 1. `[writable]` The fee vault PDA account
+    /// 2. `[writable]` The fee recipient account
+    /// 3. `[]` Rent sysvar
+    CollectFees,
+
+    /// Create the receipt mint and start minting `rate` receipt token base units
+    /// per deposited lamport
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The receipt config PDA account
+    /// 3. `[writable]` The receipt mint PDA account
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` SPL Token program
+    /// 6. `[]` System program
+    /// 7. ..7+M `[signer]` Multisig signers, when multisig is enabled
+    InitializeReceipts { rate: u64 },
+
+    /// Change the receipt rate, zero stops minting receipts
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The receipt config PDA account
+    /// 3. ..3+M `[signer]` Multisig signers, when multisig is enabled
+    SetReceiptRate { rate: u64 },
+
+    /// Move the lamports of the deposit account created before campaigns had ids
+    /// to the deposit account of the campaign and close it. The legacy history is
+    /// migrated into the same campaign afterwards. Only one campaign can adopt it
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin account
+    /// 1. `[writable]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[writable]` The legacy deposit PDA account
+    /// 4. `[writable]` The deposit PDA account of the campaign
+    /// 5. ..5+M `[signer]` Multisig signers, when multisig is enabled
+    AdoptLegacyDeposit { campaign_id: u64 },
+
+    /// Take the tokens of one mint deposited to the campaign back after the deadline
+    /// has passed without reaching the goal of the mint
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The donor account
+    /// 1. `[]` The config account
+    /// 2. `[]` The campaign account
+    /// 3. `[]` The deposit accumulate account, owner of the vault
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The vault
+    /// 6. `[writable]` The donor's destination token account
+    /// 7. `[writable]` The token totals PDA account of the mint
+    /// 8. `[writable]` The donor's token record PDA account
+    /// 9. `[]` Clock sysvar
+    /// 10. `[]` Token program owning the mint, SPL Token or Token-2022
+    ReclaimToken { campaign_id: u64 },
+
+    /// Close `batch` lamports donor records of the campaign once the deposit account holds
+    /// nothing but its rent, the rent of each record goes back to its donor
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[]` The deposit accumulate account
+    /// 4. `[]` Rent sysvar
+    /// 5. ..5+2*batch `[writable]` The donor account followed by its `[writable]` donor
+    ///    record account, for each of up to 8 donors, followed by `[signer]` multisig
+    ///    signers when multisig is enabled
+    CloseDonorRecords { campaign_id: u64, batch: u8 },
+
+    /// Close `batch` token donor records of one mint once its vault is empty, the rent of
+    /// each record goes back to its donor. After the last record the vault and the token
+    /// totals account are closed too and their rent goes to the recipient
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin account
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The campaign account
+    /// 3. `[]` The deposit accumulate account, owner of the vault
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The vault
+    /// 6. `[writable]` The token totals PDA account of the mint
+    /// 7. `[writable]` The recipient account
+    /// 8. `[]` Token program owning the mint, SPL Token or Token-2022
+    /// 9. ..9+2*batch `[writable]` The donor account followed by its `[writable]` token
+    ///    record account, for each of up to 8 donors, followed by `[signer]` multisig
+    ///    signers when multisig is enabled
+    CloseToken { campaign_id: u64, batch: u8 },
+
+    /// Stop a subscription. The subscription is revoked as the delegate of the donor's
+    /// token account and its rent goes back to the donor
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The donor account
+    /// 1. `[writable]` The subscription PDA account
+    /// 2. `[writable]` The donor's token account
+    /// 3. `[]` The mint account
+    /// 4. `[]` Token program owning the mint, SPL Token or Token-2022
+    CancelSubscription { campaign_id: u64 },
+
+    /// Apply the multisig change queued by `SetMultisig` once the timelock has passed.
+    /// Anybody can call it
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[]` Clock sysvar
+    ApplyMultisig,
+
+    /// Create a proposal to send `amount` tokens of `mint` deposited to the campaign
+    /// to the `destination` token account. Approved, queued and executed like
+    /// lamports proposals, the tokens are sent once the goal of the mint is reached
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The approver account
+    /// 1. `[writable]` The config account
+    /// 2. `[writable]` The proposal account for the next proposal id
+    /// 3. `[]` Rent sysvar
+    /// 4. `[]` Clock sysvar
+    /// 5. `[]` System program
+    /// 6. `[writable]` The campaign account
+    CreateTokenWithdrawProposal {
+        campaign_id: u64,
+        mint: Pubkey,
+        amount: u64,
+        destination: Pubkey,
+        memo: String,
+    },
 }
 
 impl DepositInstructions {
-    pub fn create_deposit(user: &Pubkey, amount: u64) -> Instruction {
-        let (pda_pubkey, _) = DepositHistoryData::get_pda_pubkey_with_bump();
-        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    /// Append multisig signers to an admin instruction
+    pub fn with_signers(mut instruction: Instruction, signers: &[Pubkey]) -> Instruction {
+        instruction.accounts.extend(
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new_readonly(*signer, true)),
+        );
+        instruction
+    }
+
+    pub fn create_deposit(user: &Pubkey, campaign_id: u64, amount: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (record_pubkey, _) = DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, user);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let (receipt_config_pubkey, _) = ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        let (receipt_mint_pubkey, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
         Instruction::new_with_borsh(
             id(),
-            &DepositInstructions::Deposit { amount },
+            &DepositInstructions::Deposit {
+                campaign_id,
+                amount,
+            },
             vec![
-                AccountMeta::new(user.clone(), true),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
                 AccountMeta::new(deposit_pubkey, false),
-                AccountMeta::new(pda_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
                 AccountMeta::new(system_program::id(), false),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(
+                        AccessList::Allow,
+                        user,
+                    )
+                    .0,
+                    false,
+                ),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(AccessList::Deny, user)
+                        .0,
+                    false,
+                ),
+                AccountMeta::new(FeeVault::get_fee_vault_pubkey_with_bump().0, false),
+                AccountMeta::new_readonly(receipt_config_pubkey, false),
+                AccountMeta::new(receipt_mint_pubkey, false),
+                AccountMeta::new(
+                    get_associated_token_address(user, &receipt_mint_pubkey),
+                    false,
+                ),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
             ],
         )
     }
 
-    pub fn create_withdraw() -> Instruction {
-        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    pub fn create_withdraw(
+        admin: &Pubkey,
+        campaign_id: u64,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
         Instruction::new_with_borsh(
             id(),
-            &DepositInstructions::Withdraw,
+            &DepositInstructions::Withdraw {
+                campaign_id,
+                amount,
+            },
             vec![
-                AccountMeta::new(ADMIN_PUBKEY.clone(), true),
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
                 AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(*destination, false),
                 AccountMeta::new_readonly(sysvar::rent::id(), false),
             ],
         )
     }
 
-    pub fn create_initialize() -> Instruction {
-        let (pda_pubkey, _) = DepositHistoryData::get_pda_pubkey_with_bump();
-        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    pub fn create_initialize(
+        admin: &Pubkey,
+        campaign_id: u64,
+        goal_lamports: u64,
+        deadline: i64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
         Instruction::new_with_borsh(
             id(),
-            &DepositInstructions::Initialize,
+            &DepositInstructions::Initialize {
+                campaign_id,
+                goal_lamports,
+                deadline,
+            },
             vec![
-                AccountMeta::new(ADMIN_PUBKEY.clone(), true),
-                AccountMeta::new(pda_pubkey, false),
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
                 AccountMeta::new(deposit_pubkey, false),
                 AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
                 AccountMeta::new_readonly(system_program::id(), false),
             ],
         )
     }
-}
 
-#[cfg(test)]
-mod test {
-    use borsh::BorshSerialize;
+    pub fn create_initialize_config(admin: &Pubkey, withdraw_threshold: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::InitializeConfig { withdraw_threshold },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(Config::get_program_data_pubkey(), false),
+            ],
+        )
+    }
 
-    use crate::instruction::DepositInstructions;
+    pub fn create_propose_admin(admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ProposeAdmin {
+                new_admin: *new_admin,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
 
-    #[test]
-    fn test_serialization_make_donation() {
-        let data = DepositInstructions::Deposit { amount: 99 }
-            .try_to_vec()
-            .unwrap();
-        assert_eq!(data, [0, 99, 0, 0, 0, 0, 0, 0, 0]);
+    pub fn create_set_multisig(admin: &Pubkey, signers: &[Pubkey], threshold: u8) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetMultisig {
+                signers: signers.to_vec(),
+                threshold,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
     }
 
-    #[test]
-    fn test_serialization_create_donation_account() {
-        let data = DepositInstructions::Initialize.try_to_vec().unwrap();
-        assert_eq!(data, [2]);
+    pub fn create_apply_multisig() -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ApplyMultisig,
+            vec![
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_withdraw_proposal(
+        approver: &Pubkey,
+        proposal_id: u64,
+        campaign_id: u64,
+        amount: u64,
+        destination: &Pubkey,
+        memo: &str,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CreateWithdrawProposal {
+                campaign_id,
+                amount,
+                destination: *destination,
+                memo: memo.to_string(),
+            },
+            vec![
+                AccountMeta::new(*approver, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(campaign_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_token_withdraw_proposal(
+        approver: &Pubkey,
+        proposal_id: u64,
+        campaign_id: u64,
+        mint: &Pubkey,
+        amount: u64,
+        destination: &Pubkey,
+        memo: &str,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CreateTokenWithdrawProposal {
+                campaign_id,
+                mint: *mint,
+                amount,
+                destination: *destination,
+                memo: memo.to_string(),
+            },
+            vec![
+                AccountMeta::new(*approver, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(campaign_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_approve_proposal(approver: &Pubkey, proposal_id: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ApproveProposal { proposal_id },
+            vec![
+                AccountMeta::new_readonly(*approver, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_reject_proposal(
+        approver: &Pubkey,
+        proposal_id: u64,
+        campaign_id: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::RejectProposal { proposal_id },
+            vec![
+                AccountMeta::new_readonly(*approver, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new(campaign_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_execute_proposal(
+        proposal_id: u64,
+        campaign_id: u64,
+        destination: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ExecuteProposal { proposal_id },
+            vec![
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_execute_token_proposal(
+        proposal_id: u64,
+        campaign_id: u64,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        destination: &Pubkey,
+    ) -> Instruction {
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        let mut instruction = Self::create_execute_proposal(proposal_id, campaign_id, destination);
+        instruction.accounts.extend([
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(totals_pubkey, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ]);
+        instruction
+    }
+
+    pub fn create_set_timelock(admin: &Pubkey, delay: i64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetTimelock { delay },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_cancel_proposal(
+        admin: &Pubkey,
+        proposal_id: u64,
+        campaign_id: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CancelProposal { proposal_id },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_pause(admin: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Pause,
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_unpause(admin: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Unpause,
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_refund(
+        admin: &Pubkey,
+        campaign_id: u64,
+        donor: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (record_pubkey, _) = DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Refund {
+                campaign_id,
+                donor: *donor,
+                amount,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new(*donor, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ]
+            .into_iter()
+            .chain(Self::receipt_burn_accounts(donor))
+            .collect(),
+        )
+    }
+
+    pub fn create_set_refunds_enabled(admin: &Pubkey, enabled: bool) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetRefundsEnabled { enabled },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_set_deposit_limits(
+        admin: &Pubkey,
+        min_deposit: u64,
+        max_deposit_per_tx: u64,
+        max_total_per_donor: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetDepositLimits {
+                min_deposit,
+                max_deposit_per_tx,
+                max_total_per_donor,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_claim_refund(donor: &Pubkey, campaign_id: u64, amount: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (record_pubkey, _) = DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ClaimRefund {
+                campaign_id,
+                amount,
+            },
+            vec![
+                AccountMeta::new(*donor, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ]
+            .into_iter()
+            .chain(Self::receipt_burn_accounts(donor))
+            .collect(),
+        )
+    }
+
+    pub fn create_reclaim(donor: &Pubkey, campaign_id: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (record_pubkey, _) = DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Reclaim { campaign_id },
+            vec![
+                AccountMeta::new(*donor, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ]
+            .into_iter()
+            .chain(Self::receipt_burn_accounts(donor))
+            .collect(),
+        )
+    }
+
+    /// Accounts refunds need to burn the donor's receipts
+    fn receipt_burn_accounts(donor: &Pubkey) -> [AccountMeta; 3] {
+        let (receipt_mint_pubkey, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+        [
+            AccountMeta::new(receipt_mint_pubkey, false),
+            AccountMeta::new(
+                get_associated_token_address(donor, &receipt_mint_pubkey),
+                false,
+            ),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]
+    }
+
+    pub fn create_initialize_token(
+        admin: &Pubkey,
+        campaign_id: u64,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        goal_amount: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::InitializeToken {
+                campaign_id,
+                goal_amount,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new_readonly(deposit_pubkey, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new(totals_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_deposit_token(
+        donor: &Pubkey,
+        campaign_id: u64,
+        source: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        let (record_pubkey, _) =
+            DonorRecord::get_token_donor_record_pubkey_with_bump(campaign_id, mint, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::DepositToken {
+                campaign_id,
+                amount,
+            },
+            vec![
+                AccountMeta::new(*donor, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new(totals_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(
+                        AccessList::Allow,
+                        donor,
+                    )
+                    .0,
+                    false,
+                ),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(
+                        AccessList::Deny,
+                        donor,
+                    )
+                    .0,
+                    false,
+                ),
+            ],
+        )
+    }
+
+    pub fn create_withdraw_token(
+        admin: &Pubkey,
+        campaign_id: u64,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::WithdrawToken {
+                campaign_id,
+                amount,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new_readonly(deposit_pubkey, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(totals_pubkey, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+        )
+    }
+
+    pub fn create_migrate_history(
+        payer: &Pubkey,
+        campaign_id: u64,
+        donors: &[Pubkey],
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (history_pubkey, _) = DepositHistoryData::get_legacy_pda_pubkey_with_bump();
+        let mut accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(campaign_pubkey, false),
+            AccountMeta::new(history_pubkey, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        for donor in donors {
+            let (record_pubkey, _) =
+                DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+            accounts.push(AccountMeta::new_readonly(*donor, false));
+            accounts.push(AccountMeta::new(record_pubkey, false));
+        }
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::MigrateHistory {
+                campaign_id,
+                batch: donors.len() as u8,
+            },
+            accounts,
+        )
+    }
+
+    pub fn create_migrate_account(payer: &Pubkey, kind: AccountKind) -> Instruction {
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::MigrateAccount { kind: kind.clone() },
+            vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new(kind.pubkey(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_close(admin: &Pubkey, campaign_id: u64, recipient: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Close { campaign_id },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new(Stream::get_stream_pubkey_with_bump(campaign_id).0, false),
+                AccountMeta::new(
+                    PayoutTable::get_payout_table_pubkey_with_bump(campaign_id).0,
+                    false,
+                ),
+            ],
+        )
+    }
+
+    pub fn create_add_to_access_list(
+        admin: &Pubkey,
+        list: AccessList,
+        donor: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (entry_pubkey, _) =
+            AccessListEntry::get_access_list_entry_pubkey_with_bump(list, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::AddToAccessList {
+                list,
+                donor: *donor,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(entry_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_remove_from_access_list(
+        admin: &Pubkey,
+        list: AccessList,
+        donor: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (entry_pubkey, _) =
+            AccessListEntry::get_access_list_entry_pubkey_with_bump(list, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::RemoveFromAccessList {
+                list,
+                donor: *donor,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(entry_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_set_allowlist_required(
+        admin: &Pubkey,
+        campaign_id: u64,
+        required: bool,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetAllowlistRequired {
+                campaign_id,
+                required,
+            },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(campaign_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_subscription(
+        donor: &Pubkey,
+        campaign_id: u64,
+        source: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+        interval: i64,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (subscription_pubkey, _) =
+            Subscription::get_subscription_pubkey_with_bump(campaign_id, mint, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CreateSubscription {
+                campaign_id,
+                amount,
+                interval,
+            },
+            vec![
+                AccountMeta::new(*donor, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(subscription_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_collect_subscription(
+        payer: &Pubkey,
+        campaign_id: u64,
+        donor: &Pubkey,
+        source: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (subscription_pubkey, _) =
+            Subscription::get_subscription_pubkey_with_bump(campaign_id, mint, donor);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        let (record_pubkey, _) =
+            DonorRecord::get_token_donor_record_pubkey_with_bump(campaign_id, mint, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CollectSubscription {
+                campaign_id,
+                donor: *donor,
+            },
+            vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(subscription_pubkey, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new(totals_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(
+                        AccessList::Allow,
+                        donor,
+                    )
+                    .0,
+                    false,
+                ),
+                AccountMeta::new_readonly(
+                    AccessListEntry::get_access_list_entry_pubkey_with_bump(
+                        AccessList::Deny,
+                        donor,
+                    )
+                    .0,
+                    false,
+                ),
+            ],
+        )
+    }
+
+    pub fn create_stream(
+        admin: &Pubkey,
+        campaign_id: u64,
+        beneficiary: &Pubkey,
+        rate: u64,
+        start: i64,
+        end: Option<i64>,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CreateStream {
+                campaign_id,
+                beneficiary: *beneficiary,
+                rate,
+                start,
+                end,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(stream_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_claim_stream(campaign_id: u64, beneficiary: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ClaimStream { campaign_id },
+            vec![
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(stream_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(*beneficiary, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_cancel_stream(admin: &Pubkey, campaign_id: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CancelStream { campaign_id },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(stream_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_set_payout_table(
+        admin: &Pubkey,
+        campaign_id: u64,
+        shares: Vec<PayoutShare>,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (payout_table_pubkey, _) = PayoutTable::get_payout_table_pubkey_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetPayoutTable {
+                campaign_id,
+                shares,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(payout_table_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_distribute(campaign_id: u64, recipients: &[Pubkey]) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (payout_table_pubkey, _) = PayoutTable::get_payout_table_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(campaign_pubkey, false),
+            AccountMeta::new_readonly(payout_table_pubkey, false),
+            AccountMeta::new(deposit_pubkey, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ];
+        accounts.extend(
+            recipients
+                .iter()
+                .map(|recipient| AccountMeta::new(*recipient, false)),
+        );
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::Distribute { campaign_id },
+            accounts,
+        )
+    }
+
+    pub fn create_set_protocol_fee(
+        admin: &Pubkey,
+        basis_points: u16,
+        recipient: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (fee_vault_pubkey, _) = FeeVault::get_fee_vault_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetProtocolFee {
+                basis_points,
+                recipient: *recipient,
+            },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new(fee_vault_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_collect_fees(recipient: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (fee_vault_pubkey, _) = FeeVault::get_fee_vault_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CollectFees,
+            vec![
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(fee_vault_pubkey, false),
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_initialize_receipts(admin: &Pubkey, rate: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (receipt_config_pubkey, _) = ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        let (receipt_mint_pubkey, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::InitializeReceipts { rate },
+            vec![
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(receipt_config_pubkey, false),
+                AccountMeta::new(receipt_mint_pubkey, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_set_receipt_rate(admin: &Pubkey, rate: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (receipt_config_pubkey, _) = ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::SetReceiptRate { rate },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new(receipt_config_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_reclaim_token(
+        donor: &Pubkey,
+        campaign_id: u64,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        destination: &Pubkey,
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        let (record_pubkey, _) =
+            DonorRecord::get_token_donor_record_pubkey_with_bump(campaign_id, mint, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::ReclaimToken { campaign_id },
+            vec![
+                AccountMeta::new_readonly(*donor, true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new_readonly(deposit_pubkey, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new(totals_pubkey, false),
+                AccountMeta::new(record_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+        )
+    }
+
+    pub fn create_close_donor_records(
+        admin: &Pubkey,
+        campaign_id: u64,
+        donors: &[Pubkey],
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(campaign_pubkey, false),
+            AccountMeta::new_readonly(deposit_pubkey, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ];
+        for donor in donors {
+            let (record_pubkey, _) =
+                DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+            accounts.push(AccountMeta::new(*donor, false));
+            accounts.push(AccountMeta::new(record_pubkey, false));
+        }
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CloseDonorRecords {
+                campaign_id,
+                batch: donors.len() as u8,
+            },
+            accounts,
+        )
+    }
+
+    pub fn create_close_token(
+        admin: &Pubkey,
+        campaign_id: u64,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        recipient: &Pubkey,
+        donors: &[Pubkey],
+    ) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        let (totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(campaign_pubkey, false),
+            AccountMeta::new_readonly(deposit_pubkey, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                DepositHistoryData::get_vault_pubkey(campaign_id, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(totals_pubkey, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+        for donor in donors {
+            let (record_pubkey, _) =
+                DonorRecord::get_token_donor_record_pubkey_with_bump(campaign_id, mint, donor);
+            accounts.push(AccountMeta::new(*donor, false));
+            accounts.push(AccountMeta::new(record_pubkey, false));
+        }
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CloseToken {
+                campaign_id,
+                batch: donors.len() as u8,
+            },
+            accounts,
+        )
+    }
+
+    pub fn create_adopt_legacy_deposit(admin: &Pubkey, campaign_id: u64) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        let (legacy_deposit_pubkey, _) = DepositHistoryData::get_legacy_deposit_with_bump();
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::AdoptLegacyDeposit { campaign_id },
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
+                AccountMeta::new(legacy_deposit_pubkey, false),
+                AccountMeta::new(deposit_pubkey, false),
+            ],
+        )
+    }
+
+    pub fn create_cancel_subscription(
+        donor: &Pubkey,
+        campaign_id: u64,
+        source: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        let (subscription_pubkey, _) =
+            Subscription::get_subscription_pubkey_with_bump(campaign_id, mint, donor);
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CancelSubscription { campaign_id },
+            vec![
+                AccountMeta::new(*donor, true),
+                AccountMeta::new(subscription_pubkey, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+        )
+    }
+
+    pub fn create_accept_admin(new_admin: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::AcceptAdmin,
+            vec![
+                AccountMeta::new_readonly(*new_admin, true),
+                AccountMeta::new(config_pubkey, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_cancel_admin_proposal(admin: &Pubkey) -> Instruction {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &DepositInstructions::CancelAdminProposal,
+            vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(config_pubkey, false),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+
+    use crate::instruction::DepositInstructions;
+
+    #[test]
+    fn test_serialization_make_donation() {
+        let data = DepositInstructions::Deposit {
+            campaign_id: 1,
+            amount: 99,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [0, 1, 0, 0, 0, 0, 0, 0, 0, 99, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_withdraw() {
+        let data = DepositInstructions::Withdraw {
+            campaign_id: 1,
+            amount: 500,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [1, 1, 0, 0, 0, 0, 0, 0, 0, 244, 1, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_create_donation_account() {
+        let data = DepositInstructions::Initialize {
+            campaign_id: 99,
+            goal_lamports: 500,
+            deadline: 1,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(
+            data,
+            [2, 99, 0, 0, 0, 0, 0, 0, 0, 244, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_serialization_initialize_config() {
+        let data = DepositInstructions::InitializeConfig {
+            withdraw_threshold: 99,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [18, 99, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_set_multisig() {
+        let data = DepositInstructions::SetMultisig {
+            signers: vec![],
+            threshold: 0,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [6, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_approve_proposal() {
+        let data = DepositInstructions::ApproveProposal { proposal_id: 5 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [8, 5, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_pause() {
+        let data = DepositInstructions::Pause.try_to_vec().unwrap();
+        assert_eq!(data, [13]);
+    }
+
+    #[test]
+    fn test_serialization_claim_refund() {
+        let data = DepositInstructions::ClaimRefund {
+            campaign_id: 1,
+            amount: 7,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [17, 1, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_reclaim() {
+        let data = DepositInstructions::Reclaim { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [19, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_deposit_token() {
+        let data = DepositInstructions::DepositToken {
+            campaign_id: 1,
+            amount: 99,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [21, 1, 0, 0, 0, 0, 0, 0, 0, 99, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_migrate_history() {
+        let data = DepositInstructions::MigrateHistory {
+            campaign_id: 1,
+            batch: 3,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [23, 1, 0, 0, 0, 0, 0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn test_serialization_close() {
+        let data = DepositInstructions::Close { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [25, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_set_deposit_limits() {
+        let data = DepositInstructions::SetDepositLimits {
+            min_deposit: 1,
+            max_deposit_per_tx: 2,
+            max_total_per_donor: 3,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(
+            data,
+            [26, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_serialization_set_allowlist_required() {
+        let data = DepositInstructions::SetAllowlistRequired {
+            campaign_id: 1,
+            required: true,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [29, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_serialization_create_subscription() {
+        let data = DepositInstructions::CreateSubscription {
+            campaign_id: 1,
+            amount: 10,
+            interval: 60,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(
+            data,
+            [30, 1, 0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 60, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_serialization_claim_stream() {
+        let data = DepositInstructions::ClaimStream { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [33, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_distribute() {
+        let data = DepositInstructions::Distribute { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [36, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_set_protocol_fee() {
+        let data = DepositInstructions::SetProtocolFee {
+            basis_points: 250,
+            recipient: Pubkey::new_from_array([1; 32]),
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data[..3], [37, 250, 0]);
+        assert_eq!(data[3..], [1; 32]);
+    }
+
+    #[test]
+    fn test_serialization_set_receipt_rate() {
+        let data = DepositInstructions::SetReceiptRate { rate: 2 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [40, 2, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_adopt_legacy_deposit() {
+        let data = DepositInstructions::AdoptLegacyDeposit { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [41, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_reclaim_token() {
+        let data = DepositInstructions::ReclaimToken { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [42, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_close_donor_records() {
+        let data = DepositInstructions::CloseDonorRecords {
+            campaign_id: 1,
+            batch: 2,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [43, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_serialization_close_token() {
+        let data = DepositInstructions::CloseToken {
+            campaign_id: 1,
+            batch: 2,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data, [44, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_serialization_cancel_subscription() {
+        let data = DepositInstructions::CancelSubscription { campaign_id: 1 }
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data, [45, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_serialization_apply_multisig() {
+        let data = DepositInstructions::ApplyMultisig.try_to_vec().unwrap();
+        assert_eq!(data, [46]);
+    }
+
+    #[test]
+    fn test_serialization_create_token_withdraw_proposal() {
+        let data = DepositInstructions::CreateTokenWithdrawProposal {
+            campaign_id: 1,
+            mint: Pubkey::new_from_array([2; 32]),
+            amount: 3,
+            destination: Pubkey::new_from_array([4; 32]),
+            memo: "m".to_string(),
+        }
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(data[0], 47);
+        assert_eq!(data.len(), 1 + 8 + 32 + 8 + 32 + 4 + 1);
+    }
+
+    #[test]
+    fn test_serialization_accept_admin() {
+        let data = DepositInstructions::AcceptAdmin.try_to_vec().unwrap();
+        assert_eq!(data, [4]);
     }
 }
@@ -1,6 +1,3 @@
-use solana_program::pubkey;
-use solana_program::pubkey::Pubkey;
-
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
@@ -8,6 +5,27 @@ pub mod processor;
 pub mod state;
 
 pub const DEPOSIT_HISTORY_SEED: &str = "deposit-history-seed";
+pub const TOKEN_TOTALS_SEED: &str = "token-totals";
+pub const DONOR_RECORD_SEED: &str = "donor";
 pub const DEPOSIT_SEED: &str = "deposit";
-pub const ADMIN_PUBKEY: Pubkey = pubkey!("3N7dHiEv6fz59uwNBTMNp9Fei9JKWL6je1fUnDxWXdbQ");
+pub const CONFIG_SEED: &str = "config";
+pub const CAMPAIGN_SEED: &str = "campaign";
+pub const PROPOSAL_SEED: &str = "proposal";
+pub const ACCESS_LIST_SEED: &str = "access";
+pub const SUBSCRIPTION_SEED: &str = "subscription";
+pub const STREAM_SEED: &str = "stream";
+pub const PAYOUT_SEED: &str = "payout";
+pub const FEE_VAULT_SEED: &str = "fee-vault";
+pub const RECEIPT_SEED: &str = "receipt";
+pub const RECEIPT_MINT_SEED: &str = "receipt-mint";
+pub const MAX_SIGNERS: usize = 10;
+pub const MAX_MEMO_LEN: usize = 128;
+pub const MAX_MIGRATE_BATCH: u8 = 8;
+pub const MAX_CLOSE_BATCH: u8 = 8;
+pub const MAX_PAYOUT_RECIPIENTS: usize = 10;
+/// Longest timelock delay, 30 days
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60;
+/// Longest interval between subscription collections, 365 days
+pub const MAX_SUBSCRIPTION_INTERVAL: i64 = 365 * 24 * 60 * 60;
+pub const RECEIPT_DECIMALS: u8 = 9;
 solana_program::declare_id!("3jYkeV2vknPL5UgFxANiNBUnRJuGeZcBP22C2gZJ1BT7");
@@ -1,18 +1,41 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::account_info::{next_account_info, AccountInfo};
+use borsh::BorshDeserialize;
+use solana_program::account_info::{next_account_info, next_account_infos, AccountInfo};
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::native_token::sol_to_lamports;
 use solana_program::program::{invoke, invoke_signed};
 use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::program_utils::limited_deserialize;
 use solana_program::pubkey::Pubkey;
 use solana_program::rent::Rent;
 use solana_program::sysvar::Sysvar;
 use solana_program::{msg, system_instruction};
 
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token::instruction::{burn, initialize_mint2, mint_to};
+use spl_token::state::Mint as ReceiptMint;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::instruction::{
+    approve_checked, close_account as close_token_account, revoke, transfer_checked,
+};
+use spl_token_2022::state::{Account as TokenAccount, Mint};
+
 use crate::error::DonationError;
 use crate::instruction::DepositInstructions;
-use crate::state::DepositHistoryData;
-use crate::{id, ADMIN_PUBKEY, DEPOSIT_HISTORY_SEED, DEPOSIT_SEED};
+use crate::state::{
+    AccessList, AccessListEntry, AccountKind, AccountState, Campaign, Config, DepositAccount,
+    DepositHistoryData, DepositTotals, DonorRecord, FeeVault, PayoutShare, PayoutTable,
+    PendingMultisig, ProposalStatus, ReceiptConfig, Stream, Subscription, WithdrawProposal,
+    CLOSED_ACCOUNT_DISCRIMINATOR, TOTAL_BASIS_POINTS,
+};
+use crate::{
+    id, ACCESS_LIST_SEED, CAMPAIGN_SEED, CONFIG_SEED, DEPOSIT_SEED, DONOR_RECORD_SEED,
+    FEE_VAULT_SEED, MAX_CLOSE_BATCH, MAX_MEMO_LEN, MAX_MIGRATE_BATCH, MAX_SIGNERS,
+    MAX_SUBSCRIPTION_INTERVAL, MAX_TIMELOCK_DELAY, PAYOUT_SEED, PROPOSAL_SEED, RECEIPT_DECIMALS,
+    RECEIPT_MINT_SEED, RECEIPT_SEED, STREAM_SEED, SUBSCRIPTION_SEED, TOKEN_TOTALS_SEED,
+};
 
 pub struct Processor;
 
@@ -26,21 +49,207 @@ impl Processor {
         msg!("Deposit: {:?}", instruction);
 
         match instruction {
-            DepositInstructions::Deposit { amount } => Self::process_deposit(accounts, amount),
-            DepositInstructions::Withdraw => Self::process_withdraw(accounts),
-            DepositInstructions::Initialize => Self::process_initialize(accounts),
+            DepositInstructions::Deposit {
+                campaign_id,
+                amount,
+            } => Self::process_deposit(accounts, campaign_id, amount),
+            DepositInstructions::Withdraw {
+                campaign_id,
+                amount,
+            } => Self::process_withdraw(accounts, campaign_id, amount),
+            DepositInstructions::Initialize {
+                campaign_id,
+                goal_lamports,
+                deadline,
+            } => Self::process_initialize(accounts, campaign_id, goal_lamports, deadline),
+            DepositInstructions::ProposeAdmin { new_admin } => {
+                Self::process_propose_admin(accounts, new_admin)
+            }
+            DepositInstructions::AcceptAdmin => Self::process_accept_admin(accounts),
+            DepositInstructions::CancelAdminProposal => {
+                Self::process_cancel_admin_proposal(accounts)
+            }
+            DepositInstructions::SetMultisig { signers, threshold } => {
+                Self::process_set_multisig(accounts, signers, threshold)
+            }
+            DepositInstructions::CreateWithdrawProposal {
+                campaign_id,
+                amount,
+                destination,
+                memo,
+            } => Self::process_create_withdraw_proposal(
+                accounts,
+                campaign_id,
+                None,
+                amount,
+                destination,
+                memo,
+            ),
+            DepositInstructions::ApproveProposal { proposal_id } => {
+                Self::process_vote_proposal(accounts, proposal_id, true)
+            }
+            DepositInstructions::RejectProposal { proposal_id } => {
+                Self::process_vote_proposal(accounts, proposal_id, false)
+            }
+            DepositInstructions::ExecuteProposal { proposal_id } => {
+                Self::process_execute_proposal(accounts, proposal_id)
+            }
+            DepositInstructions::SetTimelock { delay } => {
+                Self::process_set_timelock(accounts, delay)
+            }
+            DepositInstructions::CancelProposal { proposal_id } => {
+                Self::process_cancel_proposal(accounts, proposal_id)
+            }
+            DepositInstructions::Pause => Self::process_set_paused(accounts, true),
+            DepositInstructions::Unpause => Self::process_set_paused(accounts, false),
+            DepositInstructions::Refund {
+                campaign_id,
+                donor,
+                amount,
+            } => Self::process_refund(accounts, campaign_id, donor, amount),
+            DepositInstructions::SetDepositLimits {
+                min_deposit,
+                max_deposit_per_tx,
+                max_total_per_donor,
+            } => Self::process_set_deposit_limits(
+                accounts,
+                min_deposit,
+                max_deposit_per_tx,
+                max_total_per_donor,
+            ),
+            DepositInstructions::AddToAccessList { list, donor } => {
+                Self::process_add_to_access_list(accounts, list, donor)
+            }
+            DepositInstructions::RemoveFromAccessList { list, donor } => {
+                Self::process_remove_from_access_list(accounts, list, donor)
+            }
+            DepositInstructions::SetAllowlistRequired {
+                campaign_id,
+                required,
+            } => Self::process_set_allowlist_required(accounts, campaign_id, required),
+            DepositInstructions::SetRefundsEnabled { enabled } => {
+                Self::process_set_refunds_enabled(accounts, enabled)
+            }
+            DepositInstructions::ClaimRefund {
+                campaign_id,
+                amount,
+            } => Self::process_claim_refund(accounts, campaign_id, amount),
+            DepositInstructions::InitializeConfig { withdraw_threshold } => {
+                Self::process_initialize_config(accounts, withdraw_threshold)
+            }
+            DepositInstructions::Reclaim { campaign_id } => {
+                Self::process_reclaim(accounts, campaign_id)
+            }
+            DepositInstructions::InitializeToken {
+                campaign_id,
+                goal_amount,
+            } => Self::process_initialize_token(accounts, campaign_id, goal_amount),
+            DepositInstructions::DepositToken {
+                campaign_id,
+                amount,
+            } => Self::process_deposit_token(accounts, campaign_id, amount),
+            DepositInstructions::WithdrawToken {
+                campaign_id,
+                amount,
+            } => Self::process_withdraw_token(accounts, campaign_id, amount),
+            DepositInstructions::MigrateHistory { campaign_id, batch } => {
+                Self::process_migrate_history(accounts, campaign_id, batch)
+            }
+            DepositInstructions::Close { campaign_id } => {
+                Self::process_close(accounts, campaign_id)
+            }
+            DepositInstructions::MigrateAccount { kind } => {
+                Self::process_migrate_account(accounts, kind)
+            }
+            DepositInstructions::CreateSubscription {
+                campaign_id,
+                amount,
+                interval,
+            } => Self::process_create_subscription(accounts, campaign_id, amount, interval),
+            DepositInstructions::CollectSubscription { campaign_id, donor } => {
+                Self::process_collect_subscription(accounts, campaign_id, donor)
+            }
+            DepositInstructions::CreateStream {
+                campaign_id,
+                beneficiary,
+                rate,
+                start,
+                end,
+            } => Self::process_create_stream(accounts, campaign_id, beneficiary, rate, start, end),
+            DepositInstructions::ClaimStream { campaign_id } => {
+                Self::process_claim_stream(accounts, campaign_id)
+            }
+            DepositInstructions::CancelStream { campaign_id } => {
+                Self::process_cancel_stream(accounts, campaign_id)
+            }
+            DepositInstructions::SetPayoutTable {
+                campaign_id,
+                shares,
+            } => Self::process_set_payout_table(accounts, campaign_id, shares),
+            DepositInstructions::Distribute { campaign_id } => {
+                Self::process_distribute(accounts, campaign_id)
+            }
+            DepositInstructions::SetProtocolFee {
+                basis_points,
+                recipient,
+            } => Self::process_set_protocol_fee(accounts, basis_points, recipient),
+            DepositInstructions::CollectFees => Self::process_collect_fees(accounts),
+            DepositInstructions::InitializeReceipts { rate } => {
+                Self::process_initialize_receipts(accounts, rate)
+            }
+            DepositInstructions::SetReceiptRate { rate } => {
+                Self::process_set_receipt_rate(accounts, rate)
+            }
+            DepositInstructions::AdoptLegacyDeposit { campaign_id } => {
+                Self::process_adopt_legacy_deposit(accounts, campaign_id)
+            }
+            DepositInstructions::ReclaimToken { campaign_id } => {
+                Self::process_reclaim_token(accounts, campaign_id)
+            }
+            DepositInstructions::CloseDonorRecords { campaign_id, batch } => {
+                Self::process_close_donor_records(accounts, campaign_id, batch)
+            }
+            DepositInstructions::CloseToken { campaign_id, batch } => {
+                Self::process_close_token(accounts, campaign_id, batch)
+            }
+            DepositInstructions::CancelSubscription { campaign_id } => {
+                Self::process_cancel_subscription(accounts, campaign_id)
+            }
+            DepositInstructions::ApplyMultisig => Self::process_apply_multisig(accounts),
+            DepositInstructions::CreateTokenWithdrawProposal {
+                campaign_id,
+                mint,
+                amount,
+                destination,
+                memo,
+            } => Self::process_create_withdraw_proposal(
+                accounts,
+                campaign_id,
+                Some(mint),
+                amount,
+                destination,
+                memo,
+            ),
         }
     }
 
-    fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
-        msg!("process deposit {}", amount);
+    fn process_deposit(accounts: &[AccountInfo], campaign_id: u64, amount: u64) -> ProgramResult {
+        msg!("process deposit {} to campaign {}", amount, campaign_id);
 
         let acc_iter = &mut accounts.iter();
 
         let user_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
         let deposit_acc = next_account_info(acc_iter)?;
-        let pda_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
         let system_acc = next_account_info(acc_iter)?;
+        let allow_entry_acc = next_account_info(acc_iter)?;
+        let deny_entry_acc = next_account_info(acc_iter)?;
+        let fee_vault_acc = next_account_info(acc_iter)?;
+        let receipt_accs = acc_iter.as_slice();
 
         // Checks
         if !user_acc.is_signer {
@@ -49,169 +258,264 @@ impl Processor {
 
         msg!("User is correct");
 
-        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        let clock = Clock::from_account_info(clock_acc)?;
+        if campaign.is_ended(clock.unix_timestamp) {
+            return Err(DonationError::CampaignEnded.into());
+        }
+
+        msg!("Campaign is active");
+
+        Self::check_access(&campaign, user_acc.key, allow_entry_acc, deny_entry_acc)?;
+
+        msg!("Donor is permitted");
+
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
         if *deposit_acc.key != deposit_pubkey {
             return Err(ProgramError::InvalidArgument);
         }
 
         msg!("Deposit account is correct");
 
-        let (donation_pubkey, _) = DepositHistoryData::get_pda_pubkey_with_bump();
-        if donation_pubkey != *pda_acc.key {
+        let (record_pubkey, record_bump) =
+            DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, user_acc.key);
+        if *record_acc.key != record_pubkey {
             return Err(ProgramError::InvalidArgument);
         }
 
-        msg!("PDA account is initialized");
+        msg!("Donor record is correct");
+
+        let deposited = if record_acc.data_is_empty() {
+            0
+        } else {
+            let record = DonorRecord::unpack(&record_acc.data.borrow())?;
+            // Records upgraded from before gross amounts were kept only have the net amount
+            record.gross_amount.max(record.amount)
+        };
+        Self::check_deposit_limits(&config, amount, deposited)?;
+
+        msg!("Deposit is within limits");
+
+        let fee = config.protocol_fee(amount);
+        let net_amount = amount - fee;
+        if fee > 0 {
+            let (fee_vault_pubkey, _) = FeeVault::get_fee_vault_pubkey_with_bump();
+            if *fee_vault_acc.key != fee_vault_pubkey {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let mut fee_vault = FeeVault::unpack(&fee_vault_acc.data.borrow())?;
+
+            msg!("Fee vault is correct");
+
+            invoke(
+                &system_instruction::transfer(user_acc.key, fee_vault_acc.key, fee),
+                &[user_acc.clone(), fee_vault_acc.clone(), system_acc.clone()],
+            )?;
+
+            fee_vault.total_collected += fee;
+            fee_vault.pack(&mut fee_vault_acc.data.borrow_mut())?;
+
+            msg!("Fee vault is serialized");
+        }
 
         invoke(
-            &system_instruction::transfer(user_acc.key, deposit_acc.key, amount),
+            &system_instruction::transfer(user_acc.key, deposit_acc.key, net_amount),
             &[user_acc.clone(), deposit_acc.clone(), system_acc.clone()],
         )?;
 
         msg!(
-            "transfer {} lamports from {:?} to {:?}: done",
-            amount,
+            "transfer {} lamports from {:?} to {:?}: done, gross {}, fee {}",
+            net_amount,
             user_acc.key,
-            deposit_acc.key
+            deposit_acc.key,
+            amount,
+            fee
         );
 
-        let mut deposit_history_data: DepositHistoryData =
-            DepositHistoryData::deserialize(&mut &pda_acc.data.borrow()[..])?;
+        let record_seeds: &[&[_]] = &[
+            DONOR_RECORD_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            user_acc.key.as_ref(),
+            &[record_bump],
+        ];
+        if Self::credit_record(
+            user_acc,
+            user_acc.key,
+            record_acc,
+            record_seeds,
+            rent_acc,
+            system_acc,
+            net_amount,
+            amount,
+        )? {
+            campaign.donors_count += 1;
+        }
+
+        // Fees are not refunded, so only what reached the deposit account is owed back
+        campaign.total_raised += net_amount;
+        campaign.total_fees += fee;
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
 
-        msg!("DepositHistoryData is deserialized");
+        msg!("Campaign is serialized");
 
-        let saved_amount = deposit_history_data
-            .history
-            .get(&user_acc.key.to_bytes())
-            .unwrap_or(&0)
-            .clone();
-        deposit_history_data
-            .history
-            .insert(user_acc.key.to_bytes(), amount + saved_amount);
-        deposit_history_data.serialize(&mut &mut pda_acc.data.borrow_mut()[..])?;
+        let receipts = Self::mint_receipts(user_acc, system_acc, receipt_accs, net_amount)?;
+        if receipts > 0 {
+            let mut record = DonorRecord::unpack(&record_acc.data.borrow())?;
+            record.receipts += receipts;
+            record.pack(&mut record_acc.data.borrow_mut())?;
 
-        msg!("DepositHistoryData is serialized");
+            msg!("DonorRecord is serialized");
+        }
 
         Ok(())
     }
 
-    fn process_withdraw(accounts: &[AccountInfo]) -> ProgramResult {
-        msg!("process withdraw");
+    fn process_withdraw(accounts: &[AccountInfo], campaign_id: u64, amount: u64) -> ProgramResult {
+        msg!("process withdraw {} from campaign {}", amount, campaign_id);
 
         let acc_iter = &mut accounts.iter();
 
         let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
         let deposit_acc = next_account_info(acc_iter)?;
+        let destination_acc = next_account_info(acc_iter)?;
         let rent_acc = next_account_info(acc_iter)?;
 
         // Checks
-        if !admin_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-
-        if *admin_acc.key != ADMIN_PUBKEY {
-            return Err(DonationError::AdminRequired.into());
-        }
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
 
         msg!("Admin is correct");
 
-        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
-        if *deposit_acc.key != deposit_pubkey {
-            return Err(ProgramError::InvalidArgument);
+        if config.timelock_delay > 0 {
+            return Err(DonationError::TimelockEnabled.into());
         }
 
-        msg!("Deposit account is correct");
-
-        let amount = **deposit_acc.lamports.borrow();
-        if amount < sol_to_lamports(0.01) {
-            return Err(ProgramError::InsufficientFunds);
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        if !campaign.is_goal_reached() {
+            return Err(DonationError::GoalNotReached.into());
         }
-        let rent = &Rent::from_account_info(rent_acc)?;
-        let amount = amount - rent.minimum_balance(0);
 
-        **deposit_acc.try_borrow_mut_lamports()? -= amount;
-        **admin_acc.try_borrow_mut_lamports()? += amount;
+        msg!("Campaign goal is reached");
 
-        msg!(
-            "withdraw {} lamports from {:?} to {:?}: done",
-            amount,
-            deposit_acc.key,
-            admin_acc.key
-        );
+        Self::check_withdraw_threshold(&config, deposit_acc)?;
 
-        Ok(())
+        Self::transfer_from_deposit(
+            &config,
+            campaign_id,
+            deposit_acc,
+            destination_acc,
+            rent_acc,
+            amount,
+        )
     }
 
-    fn process_initialize(accounts: &[AccountInfo]) -> ProgramResult {
-        msg!("process process_creation_pda");
+    fn process_initialize(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        goal_lamports: u64,
+        deadline: i64,
+    ) -> ProgramResult {
+        msg!("process process_creation_pda for campaign {}", campaign_id);
 
         let acc_iter = &mut accounts.iter();
 
         let admin_acc = next_account_info(acc_iter)?;
-        let pda_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
         let deposit_acc = next_account_info(acc_iter)?;
         let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
         let system_program_acc = next_account_info(acc_iter)?;
 
         // Checks
-        if !admin_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-
-        if *admin_acc.key != ADMIN_PUBKEY {
-            return Err(DonationError::AdminRequired.into());
-        }
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
 
         msg!("Admin is correct");
 
-        let (pda_pubkey, pda_bump) = DepositHistoryData::get_pda_pubkey_with_bump();
-        if *pda_acc.key != pda_pubkey {
+        let clock = Clock::from_account_info(clock_acc)?;
+        if deadline <= clock.unix_timestamp {
+            return Err(DonationError::InvalidDeadline.into());
+        }
+
+        let (campaign_pubkey, campaign_bump) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        if *campaign_acc.key != campaign_pubkey {
             return Err(ProgramError::InvalidArgument);
         }
 
-        if !pda_acc.data_is_empty() {
+        if !campaign_acc.data_is_empty() {
             return Err(ProgramError::AccountAlreadyInitialized);
         }
 
-        msg!("PDA is correct");
+        msg!("Campaign is correct");
 
-        let (deposit_pubkey, deposit_bump) = DepositHistoryData::get_deposit_with_bump();
+        let (deposit_pubkey, deposit_bump) = DepositHistoryData::get_deposit_with_bump(campaign_id);
         if *deposit_acc.key != deposit_pubkey {
             return Err(ProgramError::InvalidArgument);
         }
 
         msg!("Deposit is correct");
 
-        let space = 60 * 100;
         let rent = &Rent::from_account_info(rent_acc)?;
-        let lamports = rent.minimum_balance(space);
-        let signer_seeds: &[&[_]] = &[DEPOSIT_HISTORY_SEED.as_bytes(), &[pda_bump]];
+        let lamports = rent.minimum_balance(Campaign::SPACE);
+        let signer_seeds: &[&[_]] = &[
+            CAMPAIGN_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[campaign_bump],
+        ];
         invoke_signed(
             &system_instruction::create_account(
                 admin_acc.key,
-                &pda_pubkey,
+                &campaign_pubkey,
                 lamports,
-                space as u64,
+                Campaign::SPACE as u64,
                 &id(),
             ),
             &[
                 admin_acc.clone(),
-                pda_acc.clone(),
+                campaign_acc.clone(),
                 system_program_acc.clone(),
             ],
-            &[&signer_seeds],
+            &[signer_seeds],
         )?;
 
-        msg!("Created PDA account");
+        let campaign = Campaign {
+            id: campaign_id,
+            goal_lamports,
+            deadline,
+            total_raised: 0,
+            donors_count: 0,
+            total_fees: 0,
+            token_mints: 0,
+            allowlist_required: false,
+            open_proposals: 0,
+        };
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
 
-        let signer_seeds: &[&[_]] = &[DEPOSIT_SEED.as_bytes(), &[deposit_bump]];
-        let lamports = rent.minimum_balance(0);
+        msg!("Created campaign account");
+
+        let signer_seeds: &[&[_]] = &[
+            DEPOSIT_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[deposit_bump],
+        ];
+        let lamports = rent.minimum_balance(DepositAccount::SPACE);
         invoke_signed(
             &system_instruction::create_account(
                 admin_acc.key,
                 &deposit_pubkey,
                 lamports,
-                0u64,
+                DepositAccount::SPACE as u64,
                 &id(),
             ),
             &[
@@ -219,11 +523,3632 @@ impl Processor {
                 deposit_acc.clone(),
                 system_program_acc.clone(),
             ],
-            &[&signer_seeds],
+            &[signer_seeds],
         )?;
 
+        DepositAccount {}.pack(&mut deposit_acc.data.borrow_mut())?;
+
         msg!("Created deposit account");
 
         Ok(())
     }
+
+    fn process_initialize_config(
+        accounts: &[AccountInfo],
+        withdraw_threshold: u64,
+    ) -> ProgramResult {
+        msg!("process initialize config");
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let program_data_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !admin_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Self::check_upgrade_authority(admin_acc, program_data_acc)?;
+
+        msg!("Admin is the upgrade authority");
+
+        let (config_pubkey, config_bump) = Config::get_config_pubkey_with_bump();
+        if *config_acc.key != config_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !config_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Config is correct");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent.minimum_balance(Config::SPACE);
+        let signer_seeds: &[&[_]] = &[CONFIG_SEED.as_bytes(), &[config_bump]];
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &config_pubkey,
+                lamports,
+                Config::SPACE as u64,
+                &id(),
+            ),
+            &[
+                admin_acc.clone(),
+                config_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let config = Config {
+            admin: *admin_acc.key,
+            withdraw_threshold,
+            pending_admin: None,
+            signers: vec![],
+            threshold: 0,
+            proposal_count: 0,
+            timelock_delay: 0,
+            admin_proposed_at: 0,
+            paused: false,
+            refunds_enabled: false,
+            min_deposit: 0,
+            max_deposit_per_tx: 0,
+            max_total_per_donor: 0,
+            legacy_access_mode: 0,
+            fee_basis_points: 0,
+            fee_recipient: Pubkey::default(),
+            legacy_campaign_id: None,
+            pending_multisig: None,
+        };
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Created config account");
+
+        Ok(())
+    }
+
+    fn process_propose_admin(accounts: &[AccountInfo], new_admin: Pubkey) -> ProgramResult {
+        msg!("process propose admin {:?}", new_admin);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        config.pending_admin = Some(new_admin);
+        config.admin_proposed_at = clock.unix_timestamp;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_accept_admin(accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process accept admin");
+
+        let acc_iter = &mut accounts.iter();
+
+        let new_admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !new_admin_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut config = Self::load_config(config_acc)?;
+        let pending_admin = config.pending_admin.ok_or(DonationError::NoPendingAdmin)?;
+        if *new_admin_acc.key != pending_admin {
+            return Err(DonationError::PendingAdminRequired.into());
+        }
+
+        msg!("Pending admin is correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !config.is_timelock_expired(config.admin_proposed_at, clock.unix_timestamp) {
+            return Err(DonationError::TimelockNotExpired.into());
+        }
+
+        msg!("Timelock has expired");
+
+        config.admin = pending_admin;
+        config.pending_admin = None;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_cancel_admin_proposal(accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process cancel admin proposal");
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if config.pending_admin.is_none() {
+            return Err(DonationError::NoPendingAdmin.into());
+        }
+
+        config.pending_admin = None;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_set_multisig(
+        accounts: &[AccountInfo],
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> ProgramResult {
+        msg!("process set multisig {} of {:?}", threshold, signers);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let mut unique_signers = signers.clone();
+        unique_signers.sort();
+        unique_signers.dedup();
+        let is_valid = if signers.is_empty() {
+            threshold == 0
+        } else {
+            signers.len() <= MAX_SIGNERS
+                && unique_signers.len() == signers.len()
+                && threshold > 0
+                && threshold as usize <= signers.len()
+        };
+        if !is_valid {
+            return Err(DonationError::InvalidMultisig.into());
+        }
+
+        msg!("Multisig is correct");
+
+        if config.timelock_delay > 0 {
+            let clock = Clock::from_account_info(clock_acc)?;
+            config.pending_multisig = Some(PendingMultisig {
+                signers,
+                threshold,
+                proposed_at: clock.unix_timestamp,
+            });
+
+            msg!("Multisig change is queued");
+        } else {
+            config.signers = signers;
+            config.threshold = threshold;
+        }
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_apply_multisig(accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process apply multisig");
+
+        let acc_iter = &mut accounts.iter();
+
+        let config_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        let pending_multisig = config
+            .pending_multisig
+            .take()
+            .ok_or(DonationError::NoPendingMultisig)?;
+
+        msg!("Pending multisig is correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !config.is_timelock_expired(pending_multisig.proposed_at, clock.unix_timestamp) {
+            return Err(DonationError::TimelockNotExpired.into());
+        }
+
+        msg!("Timelock has expired");
+
+        config.signers = pending_multisig.signers;
+        config.threshold = pending_multisig.threshold;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_create_withdraw_proposal(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        mint: Option<Pubkey>,
+        amount: u64,
+        destination: Pubkey,
+        memo: String,
+    ) -> ProgramResult {
+        msg!(
+            "process create withdraw proposal {} of {:?} to {:?}",
+            amount,
+            mint,
+            destination
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let approver_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let proposal_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_approver(&config, approver_acc)?;
+
+        msg!("Approver is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let proposal_id = config.proposal_count;
+        let (proposal_pubkey, proposal_bump) =
+            WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        if *proposal_acc.key != proposal_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !proposal_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Proposal account is correct");
+
+        if memo.len() > MAX_MEMO_LEN {
+            return Err(DonationError::MemoTooLong.into());
+        }
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent.minimum_balance(WithdrawProposal::SPACE);
+        let signer_seeds: &[&[_]] = &[
+            PROPOSAL_SEED.as_bytes(),
+            &proposal_id.to_le_bytes(),
+            &[proposal_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                approver_acc.key,
+                &proposal_pubkey,
+                lamports,
+                WithdrawProposal::SPACE as u64,
+                &id(),
+            ),
+            &[
+                approver_acc.clone(),
+                proposal_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let mut proposal = WithdrawProposal {
+            id: proposal_id,
+            campaign_id,
+            proposer: *approver_acc.key,
+            amount,
+            destination,
+            memo,
+            approvals: vec![*approver_acc.key],
+            rejections: vec![],
+            status: ProposalStatus::Active,
+            queued_at: None,
+            mint,
+        };
+        let clock = Clock::from_account_info(clock_acc)?;
+        Self::queue_if_approved(&config, &mut proposal, &clock);
+        proposal.pack(&mut proposal_acc.data.borrow_mut())?;
+
+        msg!("Created proposal {}", proposal_id);
+
+        config.proposal_count += 1;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        // Close waits for the active proposals, they would pay out of a new campaign
+        // initialized with the same id
+        campaign.open_proposals += 1;
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    fn process_vote_proposal(
+        accounts: &[AccountInfo],
+        proposal_id: u64,
+        approve: bool,
+    ) -> ProgramResult {
+        msg!("process vote proposal {}: approve {}", proposal_id, approve);
+
+        let acc_iter = &mut accounts.iter();
+
+        let approver_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let proposal_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_approver(&config, approver_acc)?;
+
+        msg!("Approver is correct");
+
+        let mut proposal = Self::load_proposal(proposal_acc, proposal_id)?;
+        if proposal.status != ProposalStatus::Active {
+            return Err(DonationError::ProposalNotActive.into());
+        }
+
+        if proposal.approvals.contains(approver_acc.key)
+            || proposal.rejections.contains(approver_acc.key)
+        {
+            return Err(DonationError::AlreadyVoted.into());
+        }
+
+        msg!("Proposal is correct");
+
+        if approve {
+            proposal.approvals.push(*approver_acc.key);
+
+            let clock = Clock::from_account_info(clock_acc)?;
+            Self::queue_if_approved(&config, &mut proposal, &clock);
+        } else {
+            proposal.rejections.push(*approver_acc.key);
+
+            let approvers_count = config.approvers().len();
+            let max_rejections = approvers_count.saturating_sub(config.approval_threshold());
+            if proposal.rejections_count(&config) > max_rejections {
+                proposal.status = ProposalStatus::Rejected;
+                msg!("Proposal {} is rejected", proposal_id);
+
+                let campaign_acc = next_account_info(acc_iter)?;
+                Self::finish_proposal(campaign_acc, proposal.campaign_id)?;
+            }
+        }
+        proposal.pack(&mut proposal_acc.data.borrow_mut())?;
+
+        msg!("Proposal is serialized");
+
+        Ok(())
+    }
+
+    fn process_execute_proposal(accounts: &[AccountInfo], proposal_id: u64) -> ProgramResult {
+        msg!("process execute proposal {}", proposal_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let proposal_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let destination_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        let mut proposal = Self::load_proposal(proposal_acc, proposal_id)?;
+        if proposal.status != ProposalStatus::Active {
+            return Err(DonationError::ProposalNotActive.into());
+        }
+
+        let queued_at = match proposal.queued_at {
+            Some(queued_at) if proposal.approvals_count(&config) >= config.approval_threshold() => {
+                queued_at
+            }
+            _ => return Err(DonationError::NotEnoughApprovals.into()),
+        };
+
+        if *destination_acc.key != proposal.destination {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Proposal is approved");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !config.is_timelock_expired(queued_at, clock.unix_timestamp) {
+            return Err(DonationError::TimelockNotExpired.into());
+        }
+
+        msg!("Timelock has expired");
+
+        let mut campaign = Self::load_campaign(campaign_acc, proposal.campaign_id)?;
+        match proposal.mint {
+            None => {
+                if !campaign.is_goal_reached() {
+                    return Err(DonationError::GoalNotReached.into());
+                }
+
+                msg!("Campaign goal is reached");
+
+                Self::check_withdraw_threshold(&config, deposit_acc)?;
+                Self::transfer_from_deposit(
+                    &config,
+                    proposal.campaign_id,
+                    deposit_acc,
+                    destination_acc,
+                    rent_acc,
+                    proposal.amount,
+                )?;
+            }
+            Some(mint) => {
+                let mint_acc = next_account_info(acc_iter)?;
+                let vault_acc = next_account_info(acc_iter)?;
+                let token_totals_acc = next_account_info(acc_iter)?;
+                let token_program_acc = next_account_info(acc_iter)?;
+
+                if config.paused {
+                    return Err(DonationError::Paused.into());
+                }
+
+                if *mint_acc.key != mint {
+                    return Err(ProgramError::InvalidArgument);
+                }
+
+                Self::check_token_goal(proposal.campaign_id, mint_acc, token_totals_acc)?;
+                Self::transfer_from_vault(
+                    proposal.campaign_id,
+                    deposit_acc,
+                    mint_acc,
+                    vault_acc,
+                    destination_acc,
+                    token_program_acc,
+                    proposal.amount,
+                )?;
+            }
+        }
+
+        proposal.status = ProposalStatus::Executed;
+        proposal.pack(&mut proposal_acc.data.borrow_mut())?;
+
+        msg!("Proposal is serialized");
+
+        campaign.open_proposals = campaign.open_proposals.saturating_sub(1);
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    fn process_set_timelock(accounts: &[AccountInfo], delay: i64) -> ProgramResult {
+        msg!("process set timelock {}", delay);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if delay < config.timelock_delay || delay > MAX_TIMELOCK_DELAY {
+            return Err(DonationError::InvalidTimelock.into());
+        }
+
+        config.timelock_delay = delay;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_cancel_proposal(accounts: &[AccountInfo], proposal_id: u64) -> ProgramResult {
+        msg!("process cancel proposal {}", proposal_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let proposal_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let mut proposal = Self::load_proposal(proposal_acc, proposal_id)?;
+        if proposal.status != ProposalStatus::Active {
+            return Err(DonationError::ProposalNotActive.into());
+        }
+
+        proposal.status = ProposalStatus::Cancelled;
+        proposal.pack(&mut proposal_acc.data.borrow_mut())?;
+
+        msg!("Proposal is serialized");
+
+        Self::finish_proposal(campaign_acc, proposal.campaign_id)
+    }
+
+    fn process_set_paused(accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+        msg!("process set paused {}", paused);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        config.paused = paused;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_refund(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        donor: Pubkey,
+        amount: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process refund {} to {:?} from campaign {}",
+            amount,
+            donor,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let donor_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let receipt_accs = next_account_infos(acc_iter, 3)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if *donor_acc.key != donor {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Donor is correct");
+
+        // The admin refunds without the donor, the donor may have moved the receipts
+        Self::debit_record(
+            campaign_id,
+            record_acc,
+            donor_acc,
+            amount,
+            receipt_accs,
+            false,
+        )?;
+        Self::debit_campaign(campaign_id, campaign_acc, amount)?;
+        Self::transfer_from_deposit(
+            &config,
+            campaign_id,
+            deposit_acc,
+            donor_acc,
+            rent_acc,
+            amount,
+        )
+    }
+
+    fn process_add_to_access_list(
+        accounts: &[AccountInfo],
+        list: AccessList,
+        donor: Pubkey,
+    ) -> ProgramResult {
+        msg!("process add {:?} to {:?} list", donor, list);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let entry_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let (entry_pubkey, entry_bump) =
+            AccessListEntry::get_access_list_entry_pubkey_with_bump(list, &donor);
+        if *entry_acc.key != entry_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !entry_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Entry is correct");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent.minimum_balance(AccessListEntry::SPACE);
+        let signer_seeds: &[&[_]] = &[
+            ACCESS_LIST_SEED.as_bytes(),
+            &[list as u8],
+            donor.as_ref(),
+            &[entry_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &entry_pubkey,
+                lamports,
+                AccessListEntry::SPACE as u64,
+                &id(),
+            ),
+            &[
+                admin_acc.clone(),
+                entry_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        AccessListEntry { list, donor }.pack(&mut entry_acc.data.borrow_mut())?;
+
+        msg!("Created access list entry");
+
+        Ok(())
+    }
+
+    fn process_remove_from_access_list(
+        accounts: &[AccountInfo],
+        list: AccessList,
+        donor: Pubkey,
+    ) -> ProgramResult {
+        msg!("process remove {:?} from {:?} list", donor, list);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let entry_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let (entry_pubkey, _) =
+            AccessListEntry::get_access_list_entry_pubkey_with_bump(list, &donor);
+        if *entry_acc.key != entry_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        AccessListEntry::unpack(&entry_acc.data.borrow())?;
+
+        msg!("Entry is correct");
+
+        Self::close_account(entry_acc, admin_acc)?;
+
+        msg!("Closed access list entry");
+
+        Ok(())
+    }
+
+    fn process_set_allowlist_required(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        required: bool,
+    ) -> ProgramResult {
+        msg!(
+            "process set allowlist required {} for campaign {}",
+            required,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        campaign.allowlist_required = required;
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    fn process_set_refunds_enabled(accounts: &[AccountInfo], enabled: bool) -> ProgramResult {
+        msg!("process set refunds enabled {}", enabled);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        config.refunds_enabled = enabled;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_set_deposit_limits(
+        accounts: &[AccountInfo],
+        min_deposit: u64,
+        max_deposit_per_tx: u64,
+        max_total_per_donor: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process set deposit limits: min {}, max per tx {}, max per donor {}",
+            min_deposit,
+            max_deposit_per_tx,
+            max_total_per_donor
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if (max_deposit_per_tx > 0 && min_deposit > max_deposit_per_tx)
+            || (max_total_per_donor > 0 && min_deposit > max_total_per_donor)
+        {
+            return Err(DonationError::InvalidDepositLimits.into());
+        }
+
+        config.min_deposit = min_deposit;
+        config.max_deposit_per_tx = max_deposit_per_tx;
+        config.max_total_per_donor = max_total_per_donor;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_claim_refund(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process claim refund {} from campaign {}",
+            amount,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let receipt_accs = next_account_infos(acc_iter, 3)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let config = Self::load_config(config_acc)?;
+        if !config.refunds_enabled {
+            return Err(DonationError::RefundsDisabled.into());
+        }
+
+        msg!("Refunds are enabled");
+
+        Self::debit_record(
+            campaign_id,
+            record_acc,
+            donor_acc,
+            amount,
+            receipt_accs,
+            true,
+        )?;
+        Self::debit_campaign(campaign_id, campaign_acc, amount)?;
+        Self::transfer_from_deposit(
+            &config,
+            campaign_id,
+            deposit_acc,
+            donor_acc,
+            rent_acc,
+            amount,
+        )
+    }
+
+    fn process_reclaim(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process reclaim from campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let receipt_accs = next_account_infos(acc_iter, 3)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let config = Self::load_config(config_acc)?;
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !campaign.is_failed(clock.unix_timestamp) {
+            return Err(DonationError::CampaignNotFailed.into());
+        }
+
+        msg!("Campaign has failed");
+
+        let amount = Self::load_donor_record(campaign_id, record_acc, donor_acc.key)?.amount;
+        if amount == 0 {
+            return Err(DonationError::NothingToReclaim.into());
+        }
+
+        msg!("Reclaiming {} lamports", amount);
+
+        // Returning the deposits of a failed campaign doesn't depend on the receipts
+        Self::debit_record(
+            campaign_id,
+            record_acc,
+            donor_acc,
+            amount,
+            receipt_accs,
+            false,
+        )?;
+        Self::debit_campaign(campaign_id, campaign_acc, amount)?;
+        Self::transfer_from_deposit(
+            &config,
+            campaign_id,
+            deposit_acc,
+            donor_acc,
+            rent_acc,
+            amount,
+        )
+    }
+
+    fn process_initialize_token(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        goal_amount: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process initialize token for campaign {} with goal {}",
+            campaign_id,
+            goal_amount
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let associated_token_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Deposit is correct");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        msg!("Vault is correct");
+
+        let (token_totals_pubkey, token_totals_bump) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !token_totals_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Token totals are correct");
+
+        invoke(
+            &create_associated_token_account(
+                admin_acc.key,
+                deposit_acc.key,
+                mint_acc.key,
+                token_program_acc.key,
+            ),
+            &[
+                admin_acc.clone(),
+                vault_acc.clone(),
+                deposit_acc.clone(),
+                mint_acc.clone(),
+                system_program_acc.clone(),
+                token_program_acc.clone(),
+                associated_token_program_acc.clone(),
+            ],
+        )?;
+
+        msg!("Created vault account");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent.minimum_balance(DepositTotals::SPACE);
+        let signer_seeds: &[&[_]] = &[
+            TOKEN_TOTALS_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            mint_acc.key.as_ref(),
+            &[token_totals_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &token_totals_pubkey,
+                lamports,
+                DepositTotals::SPACE as u64,
+                &id(),
+            ),
+            &[
+                admin_acc.clone(),
+                token_totals_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let totals = DepositTotals {
+            total_amount: 0,
+            donors_count: 0,
+            goal_amount,
+        };
+        totals.pack(&mut token_totals_acc.data.borrow_mut())?;
+
+        msg!("Created token totals account");
+
+        campaign.token_mints += 1;
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    fn process_deposit_token(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process deposit token {} to campaign {}",
+            amount,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let source_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let allow_entry_acc = next_account_info(acc_iter)?;
+        let deny_entry_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        let clock = Clock::from_account_info(clock_acc)?;
+        if campaign.is_ended(clock.unix_timestamp) {
+            return Err(DonationError::CampaignEnded.into());
+        }
+
+        msg!("Campaign is active");
+
+        Self::check_access(&campaign, donor_acc.key, allow_entry_acc, deny_entry_acc)?;
+
+        msg!("Donor is permitted");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        msg!("Vault is correct");
+
+        let (token_totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Token totals are correct");
+
+        let (record_pubkey, record_bump) = DonorRecord::get_token_donor_record_pubkey_with_bump(
+            campaign_id,
+            mint_acc.key,
+            donor_acc.key,
+        );
+        if *record_acc.key != record_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Donor record is correct");
+
+        let balance_before = Self::token_balance(vault_acc)?;
+        invoke(
+            &transfer_checked(
+                token_program_acc.key,
+                source_acc.key,
+                mint_acc.key,
+                vault_acc.key,
+                donor_acc.key,
+                &[],
+                amount,
+                Self::mint_decimals(mint_acc)?,
+            )?,
+            &[
+                source_acc.clone(),
+                mint_acc.clone(),
+                vault_acc.clone(),
+                donor_acc.clone(),
+                token_program_acc.clone(),
+            ],
+        )?;
+
+        // Transfer fee extensions withhold part of the amount in the vault,
+        // so only the balance increase is credited to the donor
+        let received = Self::token_balance(vault_acc)?.saturating_sub(balance_before);
+
+        msg!(
+            "transfer {} tokens from {:?} to {:?}: done, received {}",
+            amount,
+            source_acc.key,
+            vault_acc.key,
+            received
+        );
+
+        let record_seeds: &[&[_]] = &[
+            DONOR_RECORD_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            mint_acc.key.as_ref(),
+            donor_acc.key.as_ref(),
+            &[record_bump],
+        ];
+        let created = Self::credit_record(
+            donor_acc,
+            donor_acc.key,
+            record_acc,
+            record_seeds,
+            rent_acc,
+            system_program_acc,
+            received,
+            received,
+        )?;
+
+        Self::credit_token_totals(token_totals_acc, received, created)?;
+
+        msg!("Token totals are serialized");
+
+        Ok(())
+    }
+
+    fn process_create_subscription(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        amount: u64,
+        interval: i64,
+    ) -> ProgramResult {
+        msg!(
+            "process create subscription of {} every {} seconds to campaign {}",
+            amount,
+            interval,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let source_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let subscription_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        let clock = Clock::from_account_info(clock_acc)?;
+        if campaign.is_ended(clock.unix_timestamp) {
+            return Err(DonationError::CampaignEnded.into());
+        }
+
+        msg!("Campaign is active");
+
+        if amount == 0 || interval <= 0 || interval > MAX_SUBSCRIPTION_INTERVAL {
+            return Err(DonationError::InvalidSubscription.into());
+        }
+
+        msg!("Schedule is correct");
+
+        if *token_program_acc.key != spl_token::id()
+            && *token_program_acc.key != spl_token_2022::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if mint_acc.owner != token_program_acc.key {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Token program is correct");
+
+        let (subscription_pubkey, subscription_bump) =
+            Subscription::get_subscription_pubkey_with_bump(
+                campaign_id,
+                mint_acc.key,
+                donor_acc.key,
+            );
+        if *subscription_acc.key != subscription_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !subscription_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Subscription account is correct");
+
+        // Approving the subscription would silently replace the delegate the donor set up
+        if source_acc.owner != token_program_acc.key {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if Self::token_delegate(source_acc)?.is_some() {
+            return Err(DonationError::SourceHasDelegate.into());
+        }
+
+        msg!("Source account has no delegate");
+
+        let subscription = Subscription {
+            campaign_id,
+            donor: *donor_acc.key,
+            source: *source_acc.key,
+            mint: *mint_acc.key,
+            amount,
+            interval,
+            next_collection_at: clock.unix_timestamp,
+        };
+        // Collections stop at the deadline, the delegate can't take more than they add up to
+        let allowance = subscription
+            .allowance(campaign.deadline)
+            .ok_or(DonationError::InvalidSubscription)?;
+
+        msg!("Allowance is {}", allowance);
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                donor_acc.key,
+                subscription_acc.key,
+                rent.minimum_balance(Subscription::SPACE),
+                Subscription::SPACE as u64,
+                &id(),
+            ),
+            &[
+                donor_acc.clone(),
+                subscription_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[&[
+                SUBSCRIPTION_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                mint_acc.key.as_ref(),
+                donor_acc.key.as_ref(),
+                &[subscription_bump],
+            ]],
+        )?;
+
+        subscription.pack(&mut subscription_acc.data.borrow_mut())?;
+
+        msg!("Subscription is serialized");
+
+        // The token program checks that the donor owns the source account
+        invoke(
+            &approve_checked(
+                token_program_acc.key,
+                source_acc.key,
+                mint_acc.key,
+                subscription_acc.key,
+                donor_acc.key,
+                &[],
+                allowance,
+                Self::mint_decimals(mint_acc)?,
+            )?,
+            &[
+                source_acc.clone(),
+                mint_acc.clone(),
+                subscription_acc.clone(),
+                donor_acc.clone(),
+                token_program_acc.clone(),
+            ],
+        )?;
+
+        msg!("Subscription is approved as the delegate");
+
+        Ok(())
+    }
+
+    fn process_collect_subscription(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        donor: Pubkey,
+    ) -> ProgramResult {
+        msg!(
+            "process collect subscription of {:?} to campaign {}",
+            donor,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let payer_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let subscription_acc = next_account_info(acc_iter)?;
+        let source_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let allow_entry_acc = next_account_info(acc_iter)?;
+        let deny_entry_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !payer_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Payer is correct");
+
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        let clock = Clock::from_account_info(clock_acc)?;
+        if campaign.is_ended(clock.unix_timestamp) {
+            return Err(DonationError::CampaignEnded.into());
+        }
+
+        msg!("Campaign is active");
+
+        Self::check_access(&campaign, &donor, allow_entry_acc, deny_entry_acc)?;
+
+        msg!("Donor is permitted");
+
+        let (subscription_pubkey, subscription_bump) =
+            Subscription::get_subscription_pubkey_with_bump(campaign_id, mint_acc.key, &donor);
+        if *subscription_acc.key != subscription_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut subscription = Subscription::unpack(&subscription_acc.data.borrow())?;
+        if subscription.source != *source_acc.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Subscription is correct");
+
+        if !subscription.is_due(clock.unix_timestamp) {
+            return Err(DonationError::SubscriptionNotDue.into());
+        }
+
+        msg!("Subscription is due");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        msg!("Vault is correct");
+
+        let (token_totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Token totals are correct");
+
+        let (record_pubkey, record_bump) =
+            DonorRecord::get_token_donor_record_pubkey_with_bump(campaign_id, mint_acc.key, &donor);
+        if *record_acc.key != record_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Donor record is correct");
+
+        let balance_before = Self::token_balance(vault_acc)?;
+        invoke_signed(
+            &transfer_checked(
+                token_program_acc.key,
+                source_acc.key,
+                mint_acc.key,
+                vault_acc.key,
+                subscription_acc.key,
+                &[],
+                subscription.amount,
+                Self::mint_decimals(mint_acc)?,
+            )?,
+            &[
+                source_acc.clone(),
+                mint_acc.clone(),
+                vault_acc.clone(),
+                subscription_acc.clone(),
+                token_program_acc.clone(),
+            ],
+            &[&[
+                SUBSCRIPTION_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                mint_acc.key.as_ref(),
+                donor.as_ref(),
+                &[subscription_bump],
+            ]],
+        )?;
+
+        let received = Self::token_balance(vault_acc)?.saturating_sub(balance_before);
+
+        msg!(
+            "transfer {} tokens from {:?} to {:?}: done, received {}",
+            subscription.amount,
+            source_acc.key,
+            vault_acc.key,
+            received
+        );
+
+        let record_seeds: &[&[_]] = &[
+            DONOR_RECORD_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            mint_acc.key.as_ref(),
+            donor.as_ref(),
+            &[record_bump],
+        ];
+        let created = Self::credit_record(
+            payer_acc,
+            &donor,
+            record_acc,
+            record_seeds,
+            rent_acc,
+            system_program_acc,
+            received,
+            received,
+        )?;
+
+        Self::credit_token_totals(token_totals_acc, received, created)?;
+
+        msg!("Token totals are serialized");
+
+        subscription
+            .advance(clock.unix_timestamp)
+            .ok_or(DonationError::InvalidSubscription)?;
+        subscription.pack(&mut subscription_acc.data.borrow_mut())?;
+
+        msg!("Next collection at {}", subscription.next_collection_at);
+
+        Ok(())
+    }
+
+    fn process_cancel_subscription(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process cancel subscription to campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let subscription_acc = next_account_info(acc_iter)?;
+        let source_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let (subscription_pubkey, _) = Subscription::get_subscription_pubkey_with_bump(
+            campaign_id,
+            mint_acc.key,
+            donor_acc.key,
+        );
+        if *subscription_acc.key != subscription_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let subscription = Subscription::unpack(&subscription_acc.data.borrow())?;
+        if subscription.source != *source_acc.key {
+            return Err(DonationError::InvalidSubscription.into());
+        }
+
+        msg!("Subscription is correct");
+
+        if *token_program_acc.key != spl_token::id()
+            && *token_program_acc.key != spl_token_2022::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if source_acc.owner != token_program_acc.key {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Token program is correct");
+
+        // The donor may have approved someone else since, that delegate is left alone
+        if Self::token_delegate(source_acc)? == Some(subscription_pubkey) {
+            invoke(
+                &revoke(token_program_acc.key, source_acc.key, donor_acc.key, &[])?,
+                &[
+                    source_acc.clone(),
+                    donor_acc.clone(),
+                    token_program_acc.clone(),
+                ],
+            )?;
+
+            msg!("Subscription delegate is revoked");
+        }
+
+        Self::close_account(subscription_acc, donor_acc)
+    }
+
+    fn process_create_stream(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        beneficiary: Pubkey,
+        rate: u64,
+        start: i64,
+        end: Option<i64>,
+    ) -> ProgramResult {
+        msg!(
+            "process create stream of {} lamports per second to {:?} from campaign {}",
+            rate,
+            beneficiary,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let stream_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        // Like a queued withdrawal, nothing is paid before the timelock passes
+        let clock = Clock::from_account_info(clock_acc)?;
+        if rate == 0
+            || end.is_some_and(|end| end <= start)
+            || (config.timelock_delay > 0
+                && !config.is_timelock_expired(clock.unix_timestamp, start))
+        {
+            return Err(DonationError::InvalidStream.into());
+        }
+
+        msg!("Schedule is correct");
+
+        let (stream_pubkey, stream_bump) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        if *stream_acc.key != stream_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !stream_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Stream account is correct");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent.minimum_balance(Stream::SPACE);
+        let signer_seeds: &[&[_]] = &[
+            STREAM_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[stream_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &stream_pubkey,
+                lamports,
+                Stream::SPACE as u64,
+                &id(),
+            ),
+            &[
+                admin_acc.clone(),
+                stream_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let stream = Stream {
+            campaign_id,
+            beneficiary,
+            rate,
+            start,
+            end,
+            claimed_until: start,
+        };
+        stream.pack(&mut stream_acc.data.borrow_mut())?;
+
+        msg!("Stream is serialized");
+
+        Ok(())
+    }
+
+    fn process_claim_stream(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process claim stream of campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let stream_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let beneficiary_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        if !campaign.is_goal_reached() {
+            return Err(DonationError::GoalNotReached.into());
+        }
+
+        msg!("Campaign goal is reached");
+
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        if *stream_acc.key != stream_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut stream = Stream::unpack(&stream_acc.data.borrow())?;
+        if *beneficiary_acc.key != stream.beneficiary {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Stream is correct");
+
+        Self::check_withdraw_threshold(&config, deposit_acc)?;
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let available = deposit_acc
+            .lamports()
+            .saturating_sub(rent.minimum_balance(DepositAccount::SPACE));
+        let clock = Clock::from_account_info(clock_acc)?;
+        let amount = stream.claim(clock.unix_timestamp, available);
+        if amount == 0 {
+            return Err(DonationError::NothingAccrued.into());
+        }
+
+        Self::transfer_from_deposit(
+            &config,
+            campaign_id,
+            deposit_acc,
+            beneficiary_acc,
+            rent_acc,
+            amount,
+        )?;
+
+        stream.pack(&mut stream_acc.data.borrow_mut())?;
+
+        msg!("Stream is claimed until {}", stream.claimed_until);
+
+        Ok(())
+    }
+
+    fn process_cancel_stream(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process cancel stream of campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let stream_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        if *stream_acc.key != stream_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Stream::unpack(&stream_acc.data.borrow())?;
+
+        msg!("Stream is correct");
+
+        Self::close_account(stream_acc, admin_acc)?;
+
+        msg!("Closed stream");
+
+        Ok(())
+    }
+
+    fn process_set_payout_table(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        shares: Vec<PayoutShare>,
+    ) -> ProgramResult {
+        msg!(
+            "process set payout table of {} recipients for campaign {}",
+            shares.len(),
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let payout_table_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        let payout_table = PayoutTable {
+            shares,
+            updated_at: clock.unix_timestamp,
+        };
+        if !payout_table.is_valid() {
+            return Err(DonationError::InvalidPayoutTable.into());
+        }
+
+        msg!("Shares are correct");
+
+        let (payout_table_pubkey, payout_table_bump) =
+            PayoutTable::get_payout_table_pubkey_with_bump(campaign_id);
+        if *payout_table_acc.key != payout_table_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if payout_table_acc.data_is_empty() {
+            let rent = &Rent::from_account_info(rent_acc)?;
+            let lamports = rent.minimum_balance(PayoutTable::SPACE);
+            let signer_seeds: &[&[_]] = &[
+                PAYOUT_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                &[payout_table_bump],
+            ];
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin_acc.key,
+                    &payout_table_pubkey,
+                    lamports,
+                    PayoutTable::SPACE as u64,
+                    &id(),
+                ),
+                &[
+                    admin_acc.clone(),
+                    payout_table_acc.clone(),
+                    system_program_acc.clone(),
+                ],
+                &[signer_seeds],
+            )?;
+
+            msg!("Created payout table account");
+        } else {
+            PayoutTable::unpack(&payout_table_acc.data.borrow())?;
+        }
+
+        // The previous table may be longer, clear it before writing the new one
+        payout_table_acc.data.borrow_mut().fill(0);
+        payout_table.pack(&mut payout_table_acc.data.borrow_mut())?;
+
+        msg!("Payout table is serialized");
+
+        Ok(())
+    }
+
+    fn process_distribute(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process distribute campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let payout_table_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let recipient_accs = acc_iter.as_slice();
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        if !campaign.is_goal_reached() {
+            return Err(DonationError::GoalNotReached.into());
+        }
+
+        msg!("Campaign goal is reached");
+
+        let (payout_table_pubkey, _) = PayoutTable::get_payout_table_pubkey_with_bump(campaign_id);
+        if *payout_table_acc.key != payout_table_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let payout_table = PayoutTable::unpack(&payout_table_acc.data.borrow())?;
+        if recipient_accs.len() != payout_table.shares.len()
+            || recipient_accs
+                .iter()
+                .zip(&payout_table.shares)
+                .any(|(acc, share)| *acc.key != share.recipient)
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Recipients are correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !config.is_timelock_expired(payout_table.updated_at, clock.unix_timestamp) {
+            return Err(DonationError::TimelockNotExpired.into());
+        }
+
+        msg!("Timelock has expired");
+
+        Self::check_withdraw_threshold(&config, deposit_acc)?;
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let withdrawable = deposit_acc
+            .lamports()
+            .saturating_sub(rent.minimum_balance(DepositAccount::SPACE));
+        if withdrawable == 0 {
+            return Err(DonationError::InsufficientWithdrawableBalance.into());
+        }
+
+        for (recipient_acc, amount) in recipient_accs.iter().zip(payout_table.split(withdrawable)) {
+            Self::transfer_from_deposit(
+                &config,
+                campaign_id,
+                deposit_acc,
+                recipient_acc,
+                rent_acc,
+                amount,
+            )?;
+        }
+
+        msg!("Distributed {} lamports", withdrawable);
+
+        Ok(())
+    }
+
+    fn process_set_protocol_fee(
+        accounts: &[AccountInfo],
+        basis_points: u16,
+        recipient: Pubkey,
+    ) -> ProgramResult {
+        msg!(
+            "process set protocol fee {} basis points to {:?}",
+            basis_points,
+            recipient
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let fee_vault_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if basis_points > TOTAL_BASIS_POINTS {
+            return Err(DonationError::InvalidFee.into());
+        }
+
+        msg!("Fee is correct");
+
+        let (fee_vault_pubkey, fee_vault_bump) = FeeVault::get_fee_vault_pubkey_with_bump();
+        if *fee_vault_acc.key != fee_vault_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if fee_vault_acc.data_is_empty() {
+            let rent = &Rent::from_account_info(rent_acc)?;
+            let lamports = rent.minimum_balance(FeeVault::SPACE);
+            let signer_seeds: &[&[_]] = &[FEE_VAULT_SEED.as_bytes(), &[fee_vault_bump]];
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin_acc.key,
+                    &fee_vault_pubkey,
+                    lamports,
+                    FeeVault::SPACE as u64,
+                    &id(),
+                ),
+                &[
+                    admin_acc.clone(),
+                    fee_vault_acc.clone(),
+                    system_program_acc.clone(),
+                ],
+                &[signer_seeds],
+            )?;
+
+            let fee_vault = FeeVault { total_collected: 0 };
+            fee_vault.pack(&mut fee_vault_acc.data.borrow_mut())?;
+
+            msg!("Created fee vault account");
+        }
+
+        config.fee_basis_points = basis_points;
+        config.fee_recipient = recipient;
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    fn process_collect_fees(accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process collect fees");
+
+        let acc_iter = &mut accounts.iter();
+
+        let config_acc = next_account_info(acc_iter)?;
+        let fee_vault_acc = next_account_info(acc_iter)?;
+        let recipient_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        if *recipient_acc.key != config.fee_recipient {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Fee recipient is correct");
+
+        let (fee_vault_pubkey, _) = FeeVault::get_fee_vault_pubkey_with_bump();
+        if *fee_vault_acc.key != fee_vault_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        FeeVault::unpack(&fee_vault_acc.data.borrow())?;
+
+        msg!("Fee vault is correct");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let amount = fee_vault_acc
+            .lamports()
+            .saturating_sub(rent.minimum_balance(FeeVault::SPACE));
+        if amount == 0 {
+            return Err(DonationError::InsufficientWithdrawableBalance.into());
+        }
+
+        **fee_vault_acc.try_borrow_mut_lamports()? -= amount;
+        **recipient_acc.try_borrow_mut_lamports()? += amount;
+
+        msg!(
+            "collect {} lamports of fees to {:?}: done",
+            amount,
+            recipient_acc.key
+        );
+
+        Ok(())
+    }
+
+    fn process_initialize_receipts(accounts: &[AccountInfo], rate: u64) -> ProgramResult {
+        msg!("process initialize receipts with rate {}", rate);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let receipt_config_acc = next_account_info(acc_iter)?;
+        let receipt_mint_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let (receipt_config_pubkey, receipt_config_bump) =
+            ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        if *receipt_config_acc.key != receipt_config_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !receipt_config_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Receipt config is correct");
+
+        let (receipt_mint_pubkey, receipt_mint_bump) =
+            ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+        if *receipt_mint_acc.key != receipt_mint_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *token_program_acc.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Receipt mint is correct");
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &receipt_mint_pubkey,
+                rent.minimum_balance(ReceiptMint::LEN),
+                ReceiptMint::LEN as u64,
+                token_program_acc.key,
+            ),
+            &[
+                admin_acc.clone(),
+                receipt_mint_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[&[RECEIPT_MINT_SEED.as_bytes(), &[receipt_mint_bump]]],
+        )?;
+        invoke(
+            &initialize_mint2(
+                token_program_acc.key,
+                &receipt_mint_pubkey,
+                &receipt_config_pubkey,
+                None,
+                RECEIPT_DECIMALS,
+            )?,
+            &[receipt_mint_acc.clone(), token_program_acc.clone()],
+        )?;
+
+        msg!("Created receipt mint");
+
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_acc.key,
+                &receipt_config_pubkey,
+                rent.minimum_balance(ReceiptConfig::SPACE),
+                ReceiptConfig::SPACE as u64,
+                &id(),
+            ),
+            &[
+                admin_acc.clone(),
+                receipt_config_acc.clone(),
+                system_program_acc.clone(),
+            ],
+            &[&[RECEIPT_SEED.as_bytes(), &[receipt_config_bump]]],
+        )?;
+
+        let receipt_config = ReceiptConfig {
+            mint: receipt_mint_pubkey,
+            rate,
+        };
+        receipt_config.pack(&mut receipt_config_acc.data.borrow_mut())?;
+
+        msg!("Created receipt config account");
+
+        Ok(())
+    }
+
+    fn process_set_receipt_rate(accounts: &[AccountInfo], rate: u64) -> ProgramResult {
+        msg!("process set receipt rate {}", rate);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let receipt_config_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let (receipt_config_pubkey, _) = ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        if *receipt_config_acc.key != receipt_config_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut receipt_config = ReceiptConfig::unpack(&receipt_config_acc.data.borrow())?;
+
+        msg!("Receipt config is correct");
+
+        receipt_config.rate = rate;
+        receipt_config.pack(&mut receipt_config_acc.data.borrow_mut())?;
+
+        msg!("Receipt config is serialized");
+
+        Ok(())
+    }
+
+    fn process_adopt_legacy_deposit(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process adopt legacy deposit by campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let legacy_deposit_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let mut config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if config.legacy_campaign_id.is_some() {
+            return Err(DonationError::LegacyDepositAdopted.into());
+        }
+
+        Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let (legacy_deposit_pubkey, _) = DepositHistoryData::get_legacy_deposit_with_bump();
+        if *legacy_deposit_acc.key != legacy_deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if legacy_deposit_acc.owner != &id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Legacy deposit account is correct");
+
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        DepositAccount::unpack(&deposit_acc.data.borrow())?;
+
+        msg!("Deposit account is correct");
+
+        // The legacy deposit has no data, so draining it closes the account
+        let amount = legacy_deposit_acc.lamports();
+        **legacy_deposit_acc.try_borrow_mut_lamports()? = 0;
+        **deposit_acc.try_borrow_mut_lamports()? += amount;
+
+        msg!("Moved {} lamports of the legacy deposit", amount);
+
+        config.legacy_campaign_id = Some(campaign_id);
+        config.pack(&mut config_acc.data.borrow_mut())?;
+
+        msg!("Config is serialized");
+
+        Ok(())
+    }
+
+    /// Mint receipt tokens for `amount` deposited lamports to the donor's associated
+    /// token account, creating it on the first receipt. Does nothing until receipts
+    /// are initialized or while the rate is zero
+    fn mint_receipts<'a>(
+        donor_acc: &AccountInfo<'a>,
+        system_acc: &AccountInfo<'a>,
+        receipt_accs: &[AccountInfo<'a>],
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let acc_iter = &mut receipt_accs.iter();
+
+        let receipt_config_acc = next_account_info(acc_iter)?;
+        let receipt_mint_acc = next_account_info(acc_iter)?;
+        let receipt_account_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+        let associated_token_program_acc = next_account_info(acc_iter)?;
+
+        let (receipt_config_pubkey, receipt_config_bump) =
+            ReceiptConfig::get_receipt_config_pubkey_with_bump();
+        if *receipt_config_acc.key != receipt_config_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if receipt_config_acc.data_is_empty() {
+            return Ok(0);
+        }
+
+        let receipt_config = ReceiptConfig::unpack(&receipt_config_acc.data.borrow())?;
+        let receipts = receipt_config
+            .receipt_amount(amount)
+            .ok_or(DonationError::ReceiptAmountOverflow)?;
+        if receipts == 0 {
+            return Ok(0);
+        }
+
+        if *receipt_mint_acc.key != receipt_config.mint {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *token_program_acc.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if *receipt_account_acc.key
+            != get_associated_token_address(donor_acc.key, receipt_mint_acc.key)
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Receipt accounts are correct");
+
+        if receipt_account_acc.data_is_empty() {
+            invoke(
+                &create_associated_token_account(
+                    donor_acc.key,
+                    donor_acc.key,
+                    receipt_mint_acc.key,
+                    token_program_acc.key,
+                ),
+                &[
+                    donor_acc.clone(),
+                    receipt_account_acc.clone(),
+                    receipt_mint_acc.clone(),
+                    system_acc.clone(),
+                    token_program_acc.clone(),
+                    associated_token_program_acc.clone(),
+                ],
+            )?;
+
+            msg!("Created receipt token account");
+        }
+
+        invoke_signed(
+            &mint_to(
+                token_program_acc.key,
+                receipt_mint_acc.key,
+                receipt_account_acc.key,
+                receipt_config_acc.key,
+                &[],
+                receipts,
+            )?,
+            &[
+                receipt_mint_acc.clone(),
+                receipt_account_acc.clone(),
+                receipt_config_acc.clone(),
+                token_program_acc.clone(),
+            ],
+            &[&[RECEIPT_SEED.as_bytes(), &[receipt_config_bump]]],
+        )?;
+
+        msg!("Minted {} receipt tokens to {:?}", receipts, donor_acc.key);
+
+        Ok(receipts)
+    }
+
+    /// Burn the receipts minted for the refunded share of the donor record. The donor
+    /// has to sign and still hold them, otherwise the refund fails
+    /// Burn the receipts minted for the refunded `amount`. Unless the burn is `required`,
+    /// receipts the donor can't burn, because the donor didn't sign or doesn't hold
+    /// them anymore, are recorded as unburned and the refund goes on
+    fn burn_receipts<'a>(
+        donor_acc: &AccountInfo<'a>,
+        record: &mut DonorRecord,
+        amount: u64,
+        receipt_accs: &[AccountInfo<'a>],
+        required: bool,
+    ) -> ProgramResult {
+        // The receipts kept cover what stays deposited, rounded down so that
+        // splitting a refund can't keep any extra receipts
+        let kept = (record.receipts as u128 * (record.amount - amount) as u128)
+            .checked_div(record.amount as u128)
+            .unwrap_or(0) as u64;
+        let receipts = record.receipts - kept;
+        if receipts == 0 {
+            return Ok(());
+        }
+
+        let acc_iter = &mut receipt_accs.iter();
+
+        let receipt_mint_acc = next_account_info(acc_iter)?;
+        let receipt_account_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+
+        let can_burn = donor_acc.is_signer
+            && matches!(Self::token_balance(receipt_account_acc), Ok(balance) if balance >= receipts);
+        if !required && !can_burn {
+            record.receipts -= receipts;
+            record.unburned_receipts += receipts;
+
+            msg!(
+                "{} receipt tokens of {:?} are left unburned",
+                receipts,
+                donor_acc.key
+            );
+
+            return Ok(());
+        }
+
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (receipt_mint_pubkey, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+        if *receipt_mint_acc.key != receipt_mint_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *token_program_acc.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Receipt accounts are correct");
+
+        // The token program checks that the donor owns the receipt account
+        invoke(
+            &burn(
+                token_program_acc.key,
+                receipt_account_acc.key,
+                receipt_mint_acc.key,
+                donor_acc.key,
+                &[],
+                receipts,
+            )?,
+            &[
+                receipt_account_acc.clone(),
+                receipt_mint_acc.clone(),
+                donor_acc.clone(),
+                token_program_acc.clone(),
+            ],
+        )?;
+
+        record.receipts -= receipts;
+
+        msg!("Burned {} receipt tokens of {:?}", receipts, donor_acc.key);
+
+        Ok(())
+    }
+
+    fn credit_token_totals(
+        token_totals_acc: &AccountInfo,
+        received: u64,
+        created: bool,
+    ) -> ProgramResult {
+        let mut totals = DepositTotals::unpack(&token_totals_acc.data.borrow())?;
+        totals.total_amount += received;
+        if created {
+            totals.donors_count += 1;
+        }
+        totals.pack(&mut token_totals_acc.data.borrow_mut())
+    }
+
+    fn process_withdraw_token(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        msg!(
+            "process withdraw token {} from campaign {}",
+            amount,
+            campaign_id
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let destination_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        if config.timelock_delay > 0 {
+            return Err(DonationError::TimelockEnabled.into());
+        }
+
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        Self::check_token_goal(campaign_id, mint_acc, token_totals_acc)?;
+        Self::transfer_from_vault(
+            campaign_id,
+            deposit_acc,
+            mint_acc,
+            vault_acc,
+            destination_acc,
+            token_program_acc,
+            amount,
+        )
+    }
+
+    fn process_reclaim_token(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process reclaim token from campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let donor_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let destination_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let record_acc = next_account_info(acc_iter)?;
+        let clock_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !donor_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Donor is correct");
+
+        let config = Self::load_config(config_acc)?;
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        msg!("Program is not paused");
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let (deposit_pubkey, deposit_bump) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Deposit account is correct");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        msg!("Vault is correct");
+
+        let (token_totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut totals = DepositTotals::unpack(&token_totals_acc.data.borrow())?;
+
+        msg!("Token totals are correct");
+
+        let clock = Clock::from_account_info(clock_acc)?;
+        if !campaign.is_ended(clock.unix_timestamp) || totals.is_goal_reached() {
+            return Err(DonationError::CampaignNotFailed.into());
+        }
+
+        msg!("Token goal has failed");
+
+        let (record_pubkey, _) = DonorRecord::get_token_donor_record_pubkey_with_bump(
+            campaign_id,
+            mint_acc.key,
+            donor_acc.key,
+        );
+        if *record_acc.key != record_pubkey || record_acc.data_is_empty() {
+            return Err(DonationError::NothingToReclaim.into());
+        }
+
+        let mut record = DonorRecord::unpack(&record_acc.data.borrow())?;
+        let amount = record.amount;
+        if amount == 0 {
+            return Err(DonationError::NothingToReclaim.into());
+        }
+
+        msg!("Reclaiming {} tokens", amount);
+
+        record.amount = 0;
+        record.pack(&mut record_acc.data.borrow_mut())?;
+
+        msg!("DonorRecord is serialized");
+
+        totals.total_amount = totals.total_amount.saturating_sub(amount);
+        totals.pack(&mut token_totals_acc.data.borrow_mut())?;
+
+        msg!("Token totals are serialized");
+
+        let signer_seeds: &[&[_]] = &[
+            DEPOSIT_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[deposit_bump],
+        ];
+        invoke_signed(
+            &transfer_checked(
+                token_program_acc.key,
+                vault_acc.key,
+                mint_acc.key,
+                destination_acc.key,
+                deposit_acc.key,
+                &[],
+                amount,
+                Self::mint_decimals(mint_acc)?,
+            )?,
+            &[
+                vault_acc.clone(),
+                mint_acc.clone(),
+                destination_acc.clone(),
+                deposit_acc.clone(),
+                token_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        msg!(
+            "reclaim {} tokens from {:?} to {:?}: done",
+            amount,
+            vault_acc.key,
+            destination_acc.key
+        );
+
+        Ok(())
+    }
+
+    fn process_migrate_history(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        batch: u8,
+    ) -> ProgramResult {
+        msg!(
+            "process migrate history of campaign {}, batch {}",
+            campaign_id,
+            batch
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let payer_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let history_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !payer_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Payer is correct");
+
+        if batch == 0 || batch > MAX_MIGRATE_BATCH {
+            return Err(DonationError::InvalidBatch.into());
+        }
+
+        let donor_accs = acc_iter.as_slice();
+        if donor_accs.len() != 2 * batch as usize {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        msg!("Batch is correct");
+
+        // The legacy history belongs to the campaign holding the legacy lamports
+        let config = Self::load_config(config_acc)?;
+        if config.legacy_campaign_id != Some(campaign_id) {
+            return Err(DonationError::NotLegacyCampaign.into());
+        }
+
+        msg!("Campaign adopted the legacy deposit");
+
+        let (history_pubkey, _) = DepositHistoryData::get_legacy_pda_pubkey_with_bump();
+        if *history_acc.key != history_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if history_acc.data_is_empty() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        msg!("History account is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        // Migrated entries are removed from the legacy map, so the map itself
+        // keeps the progress and a failed batch can simply be sent again
+        for pair in donor_accs.chunks(2) {
+            let (donor_acc, record_acc) = (&pair[0], &pair[1]);
+            let amount = DepositHistoryData::remove_amount(
+                &mut history_acc.data.borrow_mut(),
+                donor_acc.key,
+            )?
+            .ok_or(DonationError::DonorNotInHistory)?;
+
+            let (record_pubkey, record_bump) =
+                DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor_acc.key);
+            if *record_acc.key != record_pubkey {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let record_seeds: &[&[_]] = &[
+                DONOR_RECORD_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                donor_acc.key.as_ref(),
+                &[record_bump],
+            ];
+            if Self::credit_record(
+                payer_acc,
+                donor_acc.key,
+                record_acc,
+                record_seeds,
+                rent_acc,
+                system_program_acc,
+                amount,
+                amount,
+            )? {
+                campaign.donors_count += 1;
+            }
+            campaign.total_raised += amount;
+
+            msg!("Migrated {} lamports of {:?}", amount, donor_acc.key);
+        }
+
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        let entries_left = DepositHistoryData::entries_count(&history_acc.data.borrow())?;
+        if entries_left > 0 {
+            msg!("{} history entries left", entries_left);
+
+            return Ok(());
+        }
+
+        // The history is fully migrated, the payer gets the rent of the legacy account back
+        Self::close_account(history_acc, payer_acc)?;
+
+        msg!("Closed history account");
+
+        Ok(())
+    }
+
+    fn process_migrate_account(accounts: &[AccountInfo], kind: AccountKind) -> ProgramResult {
+        msg!("process migrate account {:?}", kind);
+
+        let acc_iter = &mut accounts.iter();
+
+        let payer_acc = next_account_info(acc_iter)?;
+        let account_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let system_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        if !payer_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        msg!("Payer is correct");
+
+        if *account_acc.key != kind.pubkey() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if account_acc.owner != &id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        msg!("Account is correct");
+
+        let upgrade = match kind {
+            AccountKind::Config => Self::upgrade_account::<Config>,
+            AccountKind::Campaign { .. } => Self::upgrade_account::<Campaign>,
+            AccountKind::Deposit { .. } => Self::upgrade_account::<DepositAccount>,
+            AccountKind::DonorRecord { .. } => Self::upgrade_account::<DonorRecord>,
+            AccountKind::DepositTotals { .. } => Self::upgrade_account::<DepositTotals>,
+            AccountKind::WithdrawProposal { .. } => Self::upgrade_account::<WithdrawProposal>,
+            AccountKind::PayoutTable { .. } => Self::upgrade_account::<PayoutTable>,
+        };
+        upgrade(payer_acc, account_acc, rent_acc, system_program_acc)
+    }
+
+    /// Rewrite the account with the current header and layout, the payer tops up the rent
+    fn upgrade_account<'a, T: AccountState>(
+        payer_acc: &AccountInfo<'a>,
+        account_acc: &AccountInfo<'a>,
+        rent_acc: &AccountInfo<'a>,
+        system_program_acc: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        if T::is_current(&account_acc.data.borrow()) {
+            msg!("Account is up to date");
+
+            return Ok(());
+        }
+
+        let state = T::unpack_outdated(&account_acc.data.borrow())?;
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let lamports = rent
+            .minimum_balance(T::SPACE)
+            .saturating_sub(account_acc.lamports());
+        if lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_acc.key, account_acc.key, lamports),
+                &[
+                    payer_acc.clone(),
+                    account_acc.clone(),
+                    system_program_acc.clone(),
+                ],
+            )?;
+        }
+
+        account_acc.realloc(T::SPACE, true)?;
+        state.pack(&mut account_acc.data.borrow_mut())?;
+
+        msg!("Account is upgraded to version {}", T::VERSION);
+
+        Ok(())
+    }
+
+    fn process_close(accounts: &[AccountInfo], campaign_id: u64) -> ProgramResult {
+        msg!("process close campaign {}", campaign_id);
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let recipient_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let stream_acc = next_account_info(acc_iter)?;
+        let payout_table_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, acc_iter.as_slice())?;
+
+        msg!("Admin is correct");
+
+        let campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(campaign_id);
+        let (payout_table_pubkey, _) = PayoutTable::get_payout_table_pubkey_with_bump(campaign_id);
+        if *stream_acc.key != stream_pubkey || *payout_table_acc.key != payout_table_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Stream and payout table accounts are correct");
+
+        Self::check_deposit_empty(campaign_id, deposit_acc, rent_acc)?;
+
+        msg!("Deposit account is empty");
+
+        // Records left behind would be credited again to a new campaign with the same id
+        if campaign.donors_count > 0 || campaign.token_mints > 0 {
+            return Err(DonationError::CampaignHasDonors.into());
+        }
+
+        msg!("Donor records and token vaults are closed");
+
+        if campaign.open_proposals > 0 {
+            return Err(DonationError::CampaignHasProposals.into());
+        }
+
+        msg!("Withdraw proposals are closed");
+
+        if [campaign_acc, deposit_acc, stream_acc, payout_table_acc]
+            .iter()
+            .any(|acc| acc.key == recipient_acc.key)
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::close_account(campaign_acc, recipient_acc)?;
+        Self::close_account(deposit_acc, recipient_acc)?;
+
+        msg!("Closed campaign and deposit accounts");
+
+        // The stream and the payout table would pay out of a new campaign with the same id
+        for acc in [stream_acc, payout_table_acc] {
+            if acc.owner == &id() && acc.lamports() > 0 {
+                Self::close_account(acc, recipient_acc)?;
+            }
+        }
+
+        msg!("Closed stream and payout table accounts");
+
+        Ok(())
+    }
+
+    fn process_close_donor_records(
+        accounts: &[AccountInfo],
+        campaign_id: u64,
+        batch: u8,
+    ) -> ProgramResult {
+        msg!(
+            "process close donor records of campaign {}, batch {}",
+            campaign_id,
+            batch
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let (donor_accs, signer_accs) = Self::split_batch(acc_iter.as_slice(), batch)?;
+
+        msg!("Batch is correct");
+
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, signer_accs)?;
+
+        msg!("Admin is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        // Records are needed for refunds while the deposit holds donations
+        Self::check_deposit_empty(campaign_id, deposit_acc, rent_acc)?;
+
+        msg!("Deposit account is empty");
+
+        for pair in donor_accs.chunks(2) {
+            let (donor_acc, record_acc) = (&pair[0], &pair[1]);
+            let (record_pubkey, _) =
+                DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor_acc.key);
+            if *record_acc.key != record_pubkey {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            DonorRecord::unpack(&record_acc.data.borrow())?;
+
+            // The donor paid the rent of the record
+            Self::close_account(record_acc, donor_acc)?;
+            campaign.donors_count = campaign.donors_count.saturating_sub(1);
+        }
+
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("{} donor records left", campaign.donors_count);
+
+        Ok(())
+    }
+
+    fn process_close_token(accounts: &[AccountInfo], campaign_id: u64, batch: u8) -> ProgramResult {
+        msg!(
+            "process close token of campaign {}, batch {}",
+            campaign_id,
+            batch
+        );
+
+        let acc_iter = &mut accounts.iter();
+
+        let admin_acc = next_account_info(acc_iter)?;
+        let config_acc = next_account_info(acc_iter)?;
+        let campaign_acc = next_account_info(acc_iter)?;
+        let deposit_acc = next_account_info(acc_iter)?;
+        let mint_acc = next_account_info(acc_iter)?;
+        let vault_acc = next_account_info(acc_iter)?;
+        let token_totals_acc = next_account_info(acc_iter)?;
+        let recipient_acc = next_account_info(acc_iter)?;
+        let token_program_acc = next_account_info(acc_iter)?;
+
+        // Checks
+        let (donor_accs, signer_accs) = Self::split_batch(acc_iter.as_slice(), batch)?;
+
+        msg!("Batch is correct");
+
+        let config = Self::load_config(config_acc)?;
+        Self::check_admin(&config, admin_acc, signer_accs)?;
+
+        msg!("Admin is correct");
+
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+
+        msg!("Campaign is correct");
+
+        let (deposit_pubkey, deposit_bump) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Deposit account is correct");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        // Token records are needed for reclaims while the vault holds tokens
+        if Self::token_balance(vault_acc)? > 0 {
+            return Err(DonationError::DepositNotEmpty.into());
+        }
+
+        msg!("Vault is empty");
+
+        let (token_totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut totals = DepositTotals::unpack(&token_totals_acc.data.borrow())?;
+
+        msg!("Token totals are correct");
+
+        if *recipient_acc.key == *token_totals_acc.key || *recipient_acc.key == *vault_acc.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        for pair in donor_accs.chunks(2) {
+            let (donor_acc, record_acc) = (&pair[0], &pair[1]);
+            let (record_pubkey, _) = DonorRecord::get_token_donor_record_pubkey_with_bump(
+                campaign_id,
+                mint_acc.key,
+                donor_acc.key,
+            );
+            if *record_acc.key != record_pubkey {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            DonorRecord::unpack(&record_acc.data.borrow())?;
+
+            // The donor paid the rent of the record
+            Self::close_account(record_acc, donor_acc)?;
+            totals.donors_count = totals.donors_count.saturating_sub(1);
+        }
+
+        if totals.donors_count > 0 {
+            totals.pack(&mut token_totals_acc.data.borrow_mut())?;
+
+            msg!("{} token records left", totals.donors_count);
+
+            return Ok(());
+        }
+
+        let signer_seeds: &[&[_]] = &[
+            DEPOSIT_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[deposit_bump],
+        ];
+        invoke_signed(
+            &close_token_account(
+                token_program_acc.key,
+                vault_acc.key,
+                recipient_acc.key,
+                deposit_acc.key,
+                &[],
+            )?,
+            &[
+                vault_acc.clone(),
+                recipient_acc.clone(),
+                deposit_acc.clone(),
+                token_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+        Self::close_account(token_totals_acc, recipient_acc)?;
+
+        msg!("Closed vault and token totals accounts");
+
+        campaign.token_mints = campaign.token_mints.saturating_sub(1);
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    /// Split the trailing accounts into `batch` donor and record pairs and multisig signers
+    fn split_batch<'b, 'a>(
+        accounts: &'b [AccountInfo<'a>],
+        batch: u8,
+    ) -> Result<(&'b [AccountInfo<'a>], &'b [AccountInfo<'a>]), ProgramError> {
+        if batch > MAX_CLOSE_BATCH {
+            return Err(DonationError::InvalidBatch.into());
+        }
+
+        let len = 2 * batch as usize;
+        if accounts.len() < len {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        Ok(accounts.split_at(len))
+    }
+
+    /// The deposit account of the campaign holds nothing but its rent
+    fn check_deposit_empty(
+        campaign_id: u64,
+        deposit_acc: &AccountInfo,
+        rent_acc: &AccountInfo,
+    ) -> ProgramResult {
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        DepositAccount::unpack(&deposit_acc.data.borrow())?;
+
+        let rent = &Rent::from_account_info(rent_acc)?;
+        if deposit_acc.lamports() > rent.minimum_balance(DepositAccount::SPACE) {
+            return Err(DonationError::DepositNotEmpty.into());
+        }
+
+        Ok(())
+    }
+
+    /// Move all lamports of the account to the recipient and mark it closed. The runtime
+    /// removes the account at the end of the transaction, until then the closed
+    /// discriminator rejects it even if lamports are sent back to it
+    fn close_account(account_acc: &AccountInfo, recipient_acc: &AccountInfo) -> ProgramResult {
+        let lamports = account_acc.lamports();
+        **account_acc.try_borrow_mut_lamports()? = 0;
+        **recipient_acc.try_borrow_mut_lamports()? += lamports;
+
+        let mut data = account_acc.try_borrow_mut_data()?;
+        data.fill(0);
+        data[..CLOSED_ACCOUNT_DISCRIMINATOR.len()].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+
+        msg!("Returned {} lamports from {:?}", lamports, account_acc.key);
+
+        Ok(())
+    }
+
+    /// Stop counting a proposal which is not active anymore against its campaign
+    fn finish_proposal(campaign_acc: &AccountInfo, campaign_id: u64) -> ProgramResult {
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        campaign.open_proposals = campaign.open_proposals.saturating_sub(1);
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    /// Decrease the amount raised by the campaign after a refund
+    fn debit_campaign(campaign_id: u64, campaign_acc: &AccountInfo, amount: u64) -> ProgramResult {
+        let mut campaign = Self::load_campaign(campaign_acc, campaign_id)?;
+        campaign.total_raised = campaign.total_raised.saturating_sub(amount);
+        campaign.pack(&mut campaign_acc.data.borrow_mut())?;
+
+        msg!("Campaign is serialized");
+
+        Ok(())
+    }
+
+    /// Increase the donor's deposited amount stored in the donor record by the net
+    /// `amount` and its history by the `gross_amount` sent before protocol fees.
+    /// The record is created on the first deposit and paid for by `payer_acc`,
+    /// returns `true` in that case
+    #[allow(clippy::too_many_arguments)]
+    fn credit_record<'a>(
+        payer_acc: &AccountInfo<'a>,
+        donor: &Pubkey,
+        record_acc: &AccountInfo<'a>,
+        record_seeds: &[&[u8]],
+        rent_acc: &AccountInfo<'a>,
+        system_program_acc: &AccountInfo<'a>,
+        amount: u64,
+        gross_amount: u64,
+    ) -> Result<bool, ProgramError> {
+        let created = record_acc.data_is_empty();
+        let mut record = if created {
+            let rent = &Rent::from_account_info(rent_acc)?;
+            let lamports = rent.minimum_balance(DonorRecord::SPACE);
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer_acc.key,
+                    record_acc.key,
+                    lamports,
+                    DonorRecord::SPACE as u64,
+                    &id(),
+                ),
+                &[
+                    payer_acc.clone(),
+                    record_acc.clone(),
+                    system_program_acc.clone(),
+                ],
+                &[record_seeds],
+            )?;
+
+            msg!("Created donor record account");
+
+            DonorRecord {
+                donor: *donor,
+                amount: 0,
+                receipts: 0,
+                gross_amount: 0,
+                unburned_receipts: 0,
+            }
+        } else {
+            DonorRecord::unpack(&record_acc.data.borrow())?
+        };
+
+        record.amount += amount;
+        record.gross_amount += gross_amount;
+        record.pack(&mut record_acc.data.borrow_mut())?;
+
+        msg!("DonorRecord is serialized");
+
+        Ok(created)
+    }
+
+    /// Decrease the donor's deposited amount stored in the donor record
+    /// and burn the receipts minted for it
+    fn debit_record<'a>(
+        campaign_id: u64,
+        record_acc: &AccountInfo,
+        donor_acc: &AccountInfo<'a>,
+        amount: u64,
+        receipt_accs: &[AccountInfo<'a>],
+        burn_required: bool,
+    ) -> ProgramResult {
+        let mut record = Self::load_donor_record(campaign_id, record_acc, donor_acc.key)?;
+        if amount > record.amount {
+            return Err(DonationError::RefundExceedsDeposit.into());
+        }
+
+        Self::burn_receipts(donor_acc, &mut record, amount, receipt_accs, burn_required)?;
+        record.amount -= amount;
+        record.pack(&mut record_acc.data.borrow_mut())?;
+
+        msg!("DonorRecord is serialized");
+
+        Ok(())
+    }
+
+    /// Reject donors on the denylist, and donors missing from the allowlist when the
+    /// campaign requires it
+    fn check_access(
+        campaign: &Campaign,
+        donor: &Pubkey,
+        allow_entry_acc: &AccountInfo,
+        deny_entry_acc: &AccountInfo,
+    ) -> ProgramResult {
+        if Self::is_listed(AccessList::Deny, donor, deny_entry_acc)? {
+            return Err(DonationError::DonorDenied.into());
+        }
+
+        if campaign.allowlist_required
+            && !Self::is_listed(AccessList::Allow, donor, allow_entry_acc)?
+        {
+            return Err(DonationError::DonorNotAllowed.into());
+        }
+
+        Ok(())
+    }
+
+    fn is_listed(
+        list: AccessList,
+        donor: &Pubkey,
+        entry_acc: &AccountInfo,
+    ) -> Result<bool, ProgramError> {
+        let (entry_pubkey, _) =
+            AccessListEntry::get_access_list_entry_pubkey_with_bump(list, donor);
+        if *entry_acc.key != entry_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Removed entries keep the closed discriminator until the end of the transaction
+        Ok(entry_acc.owner == &id() && AccessListEntry::unpack(&entry_acc.data.borrow()).is_ok())
+    }
+
+    /// `deposited` is the gross amount the donor has already deposited to the campaign
+    fn check_deposit_limits(config: &Config, amount: u64, deposited: u64) -> ProgramResult {
+        if amount == 0 || amount < config.min_deposit {
+            return Err(DonationError::DepositTooSmall.into());
+        }
+
+        if config.max_deposit_per_tx > 0 && amount > config.max_deposit_per_tx {
+            return Err(DonationError::DepositTooLarge.into());
+        }
+
+        if config.max_total_per_donor > 0
+            && deposited.saturating_add(amount) > config.max_total_per_donor
+        {
+            return Err(DonationError::DonorLimitExceeded.into());
+        }
+
+        Ok(())
+    }
+
+    fn check_withdraw_threshold(config: &Config, deposit_acc: &AccountInfo) -> ProgramResult {
+        if **deposit_acc.lamports.borrow() < config.withdraw_threshold {
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        Ok(())
+    }
+
+    /// Start the timelock once the proposal reaches the approval threshold
+    fn queue_if_approved(config: &Config, proposal: &mut WithdrawProposal, clock: &Clock) {
+        if proposal.queued_at.is_none()
+            && proposal.approvals_count(config) >= config.approval_threshold()
+        {
+            proposal.queued_at = Some(clock.unix_timestamp);
+            msg!("Proposal {} is queued", proposal.id);
+        }
+    }
+
+    /// Move `amount` lamports from the deposit account keeping it rent-exempt
+    fn transfer_from_deposit(
+        config: &Config,
+        campaign_id: u64,
+        deposit_acc: &AccountInfo,
+        destination_acc: &AccountInfo,
+        rent_acc: &AccountInfo,
+        amount: u64,
+    ) -> ProgramResult {
+        if config.paused {
+            return Err(DonationError::Paused.into());
+        }
+
+        let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        DepositAccount::unpack(&deposit_acc.data.borrow())?;
+
+        msg!("Deposit account is correct");
+
+        let balance = **deposit_acc.lamports.borrow();
+        let rent = &Rent::from_account_info(rent_acc)?;
+        let withdrawable = balance.saturating_sub(rent.minimum_balance(DepositAccount::SPACE));
+        if amount > withdrawable {
+            return Err(DonationError::InsufficientWithdrawableBalance.into());
+        }
+
+        **deposit_acc.try_borrow_mut_lamports()? -= amount;
+        **destination_acc.try_borrow_mut_lamports()? += amount;
+
+        msg!(
+            "withdraw {} lamports from {:?} to {:?}: done",
+            amount,
+            deposit_acc.key,
+            destination_acc.key
+        );
+
+        Ok(())
+    }
+
+    fn check_token_goal(
+        campaign_id: u64,
+        mint_acc: &AccountInfo,
+        token_totals_acc: &AccountInfo,
+    ) -> ProgramResult {
+        let (token_totals_pubkey, _) =
+            DepositTotals::get_token_totals_pubkey_with_bump(campaign_id, mint_acc.key);
+        if *token_totals_acc.key != token_totals_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let totals = DepositTotals::unpack(&token_totals_acc.data.borrow())?;
+        if !totals.is_goal_reached() {
+            return Err(DonationError::GoalNotReached.into());
+        }
+
+        msg!("Token goal is reached");
+
+        Ok(())
+    }
+
+    /// Move `amount` tokens from the vault, signed by the deposit account owning it
+    fn transfer_from_vault<'a>(
+        campaign_id: u64,
+        deposit_acc: &AccountInfo<'a>,
+        mint_acc: &AccountInfo<'a>,
+        vault_acc: &AccountInfo<'a>,
+        destination_acc: &AccountInfo<'a>,
+        token_program_acc: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (deposit_pubkey, deposit_bump) = DepositHistoryData::get_deposit_with_bump(campaign_id);
+        if *deposit_acc.key != deposit_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        msg!("Deposit account is correct");
+
+        Self::check_vault(campaign_id, mint_acc, vault_acc, token_program_acc)?;
+
+        msg!("Vault is correct");
+
+        let signer_seeds: &[&[_]] = &[
+            DEPOSIT_SEED.as_bytes(),
+            &campaign_id.to_le_bytes(),
+            &[deposit_bump],
+        ];
+        invoke_signed(
+            &transfer_checked(
+                token_program_acc.key,
+                vault_acc.key,
+                mint_acc.key,
+                destination_acc.key,
+                deposit_acc.key,
+                &[],
+                amount,
+                Self::mint_decimals(mint_acc)?,
+            )?,
+            &[
+                vault_acc.clone(),
+                mint_acc.clone(),
+                destination_acc.clone(),
+                deposit_acc.clone(),
+                token_program_acc.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        msg!(
+            "withdraw {} tokens from {:?} to {:?}: done",
+            amount,
+            vault_acc.key,
+            destination_acc.key
+        );
+
+        Ok(())
+    }
+
+    fn load_proposal(
+        proposal_acc: &AccountInfo,
+        proposal_id: u64,
+    ) -> Result<WithdrawProposal, ProgramError> {
+        let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(proposal_id);
+        if *proposal_acc.key != proposal_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        WithdrawProposal::unpack(&proposal_acc.data.borrow())
+    }
+
+    fn load_campaign(
+        campaign_acc: &AccountInfo,
+        campaign_id: u64,
+    ) -> Result<Campaign, ProgramError> {
+        let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(campaign_id);
+        if *campaign_acc.key != campaign_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Campaign::unpack(&campaign_acc.data.borrow())
+    }
+
+    /// Load the donor's SOL record, a missing record means nothing was deposited
+    fn load_donor_record(
+        campaign_id: u64,
+        record_acc: &AccountInfo,
+        donor: &Pubkey,
+    ) -> Result<DonorRecord, ProgramError> {
+        let (record_pubkey, _) = DonorRecord::get_donor_record_pubkey_with_bump(campaign_id, donor);
+        if *record_acc.key != record_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if record_acc.data_is_empty() {
+            return Ok(DonorRecord {
+                donor: *donor,
+                amount: 0,
+                receipts: 0,
+                gross_amount: 0,
+                unburned_receipts: 0,
+            });
+        }
+
+        DonorRecord::unpack(&record_acc.data.borrow())
+    }
+
+    fn check_vault(
+        campaign_id: u64,
+        mint_acc: &AccountInfo,
+        vault_acc: &AccountInfo,
+        token_program_acc: &AccountInfo,
+    ) -> ProgramResult {
+        if *token_program_acc.key != spl_token::id()
+            && *token_program_acc.key != spl_token_2022::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if mint_acc.owner != token_program_acc.key {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let vault_pubkey =
+            DepositHistoryData::get_vault_pubkey(campaign_id, mint_acc.key, token_program_acc.key);
+        if *vault_acc.key != vault_pubkey {
+            return Err(DonationError::InvalidVault.into());
+        }
+
+        Ok(())
+    }
+
+    /// Works for SPL Token and Token-2022 mints, extensions are ignored
+    fn mint_decimals(mint_acc: &AccountInfo) -> Result<u8, ProgramError> {
+        let data = mint_acc.data.borrow();
+        Ok(StateWithExtensions::<Mint>::unpack(&data)?.base.decimals)
+    }
+
+    fn token_balance(token_acc: &AccountInfo) -> Result<u64, ProgramError> {
+        let data = token_acc.data.borrow();
+        Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?
+            .base
+            .amount)
+    }
+
+    fn token_delegate(token_acc: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+        let data = token_acc.data.borrow();
+        Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?
+            .base
+            .delegate
+            .into())
+    }
+
+    fn check_approver(config: &Config, approver_acc: &AccountInfo) -> ProgramResult {
+        if !approver_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if !config.approvers().contains(approver_acc.key) {
+            return Err(DonationError::ApproverRequired.into());
+        }
+
+        Ok(())
+    }
+
+    /// Only the upgrade authority of the program may create the config,
+    /// otherwise anyone could front-run the upgrade and become the admin
+    fn check_upgrade_authority(
+        admin_acc: &AccountInfo,
+        program_data_acc: &AccountInfo,
+    ) -> ProgramResult {
+        if *program_data_acc.key != Config::get_program_data_pubkey()
+            || *program_data_acc.owner != bpf_loader_upgradeable::id()
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let state: UpgradeableLoaderState = limited_deserialize(
+            &program_data_acc.data.borrow(),
+            UpgradeableLoaderState::programdata_data_offset()
+                .map_err(|_| ProgramError::InvalidAccountData)? as u64,
+        )
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+        match state {
+            UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address: Some(authority),
+                ..
+            } if authority == *admin_acc.key => Ok(()),
+            UpgradeableLoaderState::ProgramData { .. } => Err(DonationError::AdminRequired.into()),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    fn load_config(config_acc: &AccountInfo) -> Result<Config, ProgramError> {
+        let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+        if *config_acc.key != config_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Config::unpack(&config_acc.data.borrow())
+    }
+
+    /// Check the admin signature, or at least `config.threshold` multisig
+    /// signatures among `admin_acc` and `signer_accs` when multisig is enabled
+    fn check_admin<'a>(
+        config: &Config,
+        admin_acc: &AccountInfo<'a>,
+        signer_accs: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        if !admin_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if !config.is_multisig() {
+            if *admin_acc.key != config.admin {
+                return Err(DonationError::AdminRequired.into());
+            }
+
+            return Ok(());
+        }
+
+        let mut approved: Vec<&Pubkey> = std::iter::once(admin_acc)
+            .chain(signer_accs)
+            .filter(|acc| acc.is_signer && config.signers.contains(acc.key))
+            .map(|acc| acc.key)
+            .collect();
+        approved.sort();
+        approved.dedup();
+
+        if approved.len() < config.threshold as usize {
+            return Err(DonationError::NotEnoughSigners.into());
+        }
+
+        Ok(())
+    }
 }
@@ -1,31 +1,816 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::bpf_loader_upgradeable;
+use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 
-use crate::{id, DEPOSIT_HISTORY_SEED, DEPOSIT_SEED};
+use crate::error::DonationError;
+use crate::{
+    id, ACCESS_LIST_SEED, CAMPAIGN_SEED, CONFIG_SEED, DEPOSIT_HISTORY_SEED, DEPOSIT_SEED,
+    DONOR_RECORD_SEED, FEE_VAULT_SEED, MAX_MEMO_LEN, MAX_PAYOUT_RECIPIENTS, MAX_SIGNERS,
+    PAYOUT_SEED, PROPOSAL_SEED, RECEIPT_MINT_SEED, RECEIPT_SEED, STREAM_SEED, SUBSCRIPTION_SEED,
+    TOKEN_TOTALS_SEED,
+};
 
+pub const ACCOUNT_HEADER_LEN: usize = 8 + 1;
+/// Written over the header of closed accounts
+pub const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [255; 8];
+
+/// State of a program-owned account. The account data starts with an 8-byte
+/// discriminator and a version byte followed by the Borsh encoded state
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+    const VERSION: u8;
+    /// Max length of the Borsh encoded state
+    const LEN: usize;
+    /// Length of the account data, header included
+    const SPACE: usize = ACCOUNT_HEADER_LEN + Self::LEN;
+
+    fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() >= ACCOUNT_HEADER_LEN && data[..8] == CLOSED_ACCOUNT_DISCRIMINATOR {
+            return Err(DonationError::AccountClosed.into());
+        }
+
+        if data.len() < ACCOUNT_HEADER_LEN || data[..8] != Self::DISCRIMINATOR {
+            return Err(DonationError::InvalidAccountDiscriminator.into());
+        }
+
+        if data[8] != Self::VERSION {
+            return Err(DonationError::UnsupportedAccountVersion.into());
+        }
+
+        Ok(Self::deserialize(&mut &data[ACCOUNT_HEADER_LEN..])?)
+    }
+
+    /// State written by an older program version, possibly before accounts had
+    /// a header. Fields added since then are zeroed
+    fn unpack_outdated(data: &[u8]) -> Result<Self, ProgramError> {
+        let body = if data.len() >= ACCOUNT_HEADER_LEN && data[..8] == Self::DISCRIMINATOR {
+            &data[ACCOUNT_HEADER_LEN..]
+        } else {
+            data
+        };
+        let mut body = body.to_vec();
+        body.resize(body.len().max(Self::LEN), 0);
+        Ok(Self::deserialize(&mut &body[..])?)
+    }
+
+    fn is_current(data: &[u8]) -> bool {
+        data.len() >= ACCOUNT_HEADER_LEN
+            && data[..8] == Self::DISCRIMINATOR
+            && data[8] == Self::VERSION
+    }
+
+    fn pack(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        data[8] = Self::VERSION;
+        self.serialize(&mut &mut data[ACCOUNT_HEADER_LEN..])?;
+        Ok(())
+    }
+}
+
+/// Program-owned account to upgrade with `MigrateAccount`, with the seeds of its address
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum AccountKind {
+    Config,
+    Campaign {
+        campaign_id: u64,
+    },
+    Deposit {
+        campaign_id: u64,
+    },
+    DonorRecord {
+        campaign_id: u64,
+        mint: Option<Pubkey>,
+        donor: Pubkey,
+    },
+    DepositTotals {
+        campaign_id: u64,
+        mint: Pubkey,
+    },
+    WithdrawProposal {
+        proposal_id: u64,
+    },
+    PayoutTable {
+        campaign_id: u64,
+    },
+}
+
+impl AccountKind {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            AccountKind::Config => Config::get_config_pubkey_with_bump().0,
+            AccountKind::Campaign { campaign_id } => {
+                Campaign::get_campaign_pubkey_with_bump(*campaign_id).0
+            }
+            AccountKind::Deposit { campaign_id } => {
+                DepositHistoryData::get_deposit_with_bump(*campaign_id).0
+            }
+            AccountKind::DonorRecord {
+                campaign_id,
+                mint: None,
+                donor,
+            } => DonorRecord::get_donor_record_pubkey_with_bump(*campaign_id, donor).0,
+            AccountKind::DonorRecord {
+                campaign_id,
+                mint: Some(mint),
+                donor,
+            } => DonorRecord::get_token_donor_record_pubkey_with_bump(*campaign_id, mint, donor).0,
+            AccountKind::DepositTotals { campaign_id, mint } => {
+                DepositTotals::get_token_totals_pubkey_with_bump(*campaign_id, mint).0
+            }
+            AccountKind::WithdrawProposal { proposal_id } => {
+                WithdrawProposal::get_proposal_pubkey_with_bump(*proposal_id).0
+            }
+            AccountKind::PayoutTable { campaign_id } => {
+                PayoutTable::get_payout_table_pubkey_with_bump(*campaign_id).0
+            }
+        }
+    }
+}
+
+/// Legacy history of all donors of a campaign in a single account. Deposits are
+/// tracked in `DonorRecord`s now, the map is only read from existing deployments
 #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
 pub struct DepositHistoryData {
     pub history: HashMap<[u8; 32], u64>,
 }
 
 impl DepositHistoryData {
-    pub fn get_pda_pubkey_with_bump() -> (Pubkey, u8) {
+    pub fn get_deposit_with_bump(campaign_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[DEPOSIT_SEED.as_bytes(), &campaign_id.to_le_bytes()],
+            &id(),
+        )
+    }
+
+    /// History account of the single campaign deployed before campaigns had ids
+    pub fn get_legacy_pda_pubkey_with_bump() -> (Pubkey, u8) {
         Pubkey::find_program_address(&[DEPOSIT_HISTORY_SEED.as_bytes()], &id())
     }
 
-    pub fn get_deposit_with_bump() -> (Pubkey, u8) {
+    /// Deposit account of the single campaign deployed before campaigns had ids
+    pub fn get_legacy_deposit_with_bump() -> (Pubkey, u8) {
         Pubkey::find_program_address(&[DEPOSIT_SEED.as_bytes()], &id())
     }
+
+    /// Associated token account of the deposit account, holds deposited tokens of one mint.
+    /// `token_program` is either SPL Token or Token-2022, whichever owns the mint
+    pub fn get_vault_pubkey(campaign_id: u64, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        let (deposit_pubkey, _) = Self::get_deposit_with_bump(campaign_id);
+        get_associated_token_address_with_program_id(&deposit_pubkey, mint, token_program)
+    }
+}
+
+/// Zero-copy access to the history account data. Borsh writes the map as a `u32`
+/// entries count followed by `{pubkey, amount}` entries sorted by pubkey, so a donor
+/// is found with a binary search and removed in place without (de)serializing the map.
+/// Deposits go to donor records, so the migration is the only remaining writer
+impl DepositHistoryData {
+    pub const HEADER_LEN: usize = 4;
+    pub const ENTRY_LEN: usize = 32 + 8;
+
+    pub fn entries_count(data: &[u8]) -> Result<usize, ProgramError> {
+        let header = data
+            .get(..Self::HEADER_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let count = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+        if Self::HEADER_LEN + count * Self::ENTRY_LEN > data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(count)
+    }
+
+    /// Remove the donor's entry, returns the removed amount
+    pub fn remove_amount(data: &mut [u8], donor: &Pubkey) -> Result<Option<u64>, ProgramError> {
+        let index = match Self::search(data, donor)? {
+            Ok(index) => index,
+            Err(_) => return Ok(None),
+        };
+
+        let amount = Self::entry_amount(data, index);
+        let count = Self::entries_count(data)?;
+        let offset = Self::entry_offset(index);
+        let end = Self::entry_offset(count);
+        data.copy_within(offset + Self::ENTRY_LEN..end, offset);
+        data[end - Self::ENTRY_LEN..end].fill(0);
+        Self::set_entries_count(data, count - 1);
+
+        Ok(Some(amount))
+    }
+
+    fn search(data: &[u8], donor: &Pubkey) -> Result<Result<usize, usize>, ProgramError> {
+        let (mut low, mut high) = (0, Self::entries_count(data)?);
+        while low < high {
+            let middle = (low + high) / 2;
+            let offset = Self::entry_offset(middle);
+            match data[offset..offset + 32].cmp(donor.as_ref()) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Ok(Ok(middle)),
+            }
+        }
+
+        Ok(Err(low))
+    }
+
+    fn entry_offset(index: usize) -> usize {
+        Self::HEADER_LEN + index * Self::ENTRY_LEN
+    }
+
+    fn entry_amount(data: &[u8], index: usize) -> u64 {
+        let offset = Self::entry_offset(index) + 32;
+        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn set_entries_count(data: &mut [u8], count: usize) {
+        data[..Self::HEADER_LEN].copy_from_slice(&(count as u32).to_le_bytes());
+    }
+}
+
+/// Deposit account of a campaign, holds deposited lamports and owns the token vaults
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct DepositAccount {}
+
+impl AccountState for DepositAccount {
+    const DISCRIMINATOR: [u8; 8] = *b"deposit_";
+    const VERSION: u8 = 1;
+    const LEN: usize = 0;
+}
+
+/// Amount deposited by one donor after protocol fees, one account per donor and campaign
+/// (and mint for token deposits). Created on the first deposit at the donor's expense
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct DonorRecord {
+    pub donor: Pubkey,
+    /// Deposited amount after protocol fees and refunds, the amount owed back on refunds
+    pub amount: u64,
+    /// Receipt tokens minted for `amount` and not burned by refunds yet
+    pub receipts: u64,
+    /// Every deposit before protocol fees, refunds don't reduce it
+    pub gross_amount: u64,
+    /// Receipt tokens of refunded deposits left with the donor because the refund
+    /// couldn't burn them
+    pub unburned_receipts: u64,
+}
+
+impl DonorRecord {
+    pub fn get_donor_record_pubkey_with_bump(campaign_id: u64, donor: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                DONOR_RECORD_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                donor.as_ref(),
+            ],
+            &id(),
+        )
+    }
+
+    pub fn get_token_donor_record_pubkey_with_bump(
+        campaign_id: u64,
+        mint: &Pubkey,
+        donor: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                DONOR_RECORD_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                mint.as_ref(),
+                donor.as_ref(),
+            ],
+            &id(),
+        )
+    }
+}
+
+impl AccountState for DonorRecord {
+    const DISCRIMINATOR: [u8; 8] = *b"donorrec";
+    const VERSION: u8 = 4;
+    const LEN: usize = 32 + 8 + 8 + 8 + 8;
+}
+
+/// Aggregated token deposits of one mint to the campaign
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct DepositTotals {
+    /// Tokens received by the vault
+    pub total_amount: u64,
+    /// Number of donor records
+    pub donors_count: u64,
+    /// Tokens the campaign has to raise in this mint before the admin may withdraw them
+    pub goal_amount: u64,
+}
+
+impl DepositTotals {
+    pub fn is_goal_reached(&self) -> bool {
+        self.total_amount >= self.goal_amount
+    }
+
+    pub fn get_token_totals_pubkey_with_bump(campaign_id: u64, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                TOKEN_TOTALS_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                mint.as_ref(),
+            ],
+            &id(),
+        )
+    }
+}
+
+impl AccountState for DepositTotals {
+    const DISCRIMINATOR: [u8; 8] = *b"totals__";
+    const VERSION: u8 = 2;
+    const LEN: usize = 8 + 8 + 8;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Campaign {
+    pub id: u64,
+    /// Lamports the campaign has to raise before the admin may withdraw
+    pub goal_lamports: u64,
+    /// Unix timestamp after which deposits are rejected
+    pub deadline: i64,
+    /// Lamports deposited after protocol fees and not refunded yet
+    pub total_raised: u64,
+    /// Number of donor records
+    pub donors_count: u64,
+    /// Protocol fees taken from the deposits, the rest went to the deposit account
+    /// and counts towards the goal
+    pub total_fees: u64,
+    /// Number of mints with a vault and a token totals account
+    pub token_mints: u64,
+    /// Only donors on the allowlist may deposit. The denylist applies to every campaign
+    pub allowlist_required: bool,
+    /// Withdraw proposals of the campaign that are still active
+    pub open_proposals: u64,
+}
+
+impl Campaign {
+    pub fn is_goal_reached(&self) -> bool {
+        self.total_raised >= self.goal_lamports
+    }
+
+    pub fn is_ended(&self, now: i64) -> bool {
+        now > self.deadline
+    }
+
+    /// The deadline has passed without reaching the goal, donors may reclaim their deposits
+    pub fn is_failed(&self, now: i64) -> bool {
+        self.is_ended(now) && !self.is_goal_reached()
+    }
+
+    pub fn get_campaign_pubkey_with_bump(campaign_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[CAMPAIGN_SEED.as_bytes(), &campaign_id.to_le_bytes()],
+            &id(),
+        )
+    }
+}
+
+impl AccountState for Campaign {
+    const DISCRIMINATOR: [u8; 8] = *b"campaign";
+    const VERSION: u8 = 5;
+    const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub withdraw_threshold: u64,
+    pub pending_admin: Option<Pubkey>,
+    /// Multisig signers. When empty the single `admin` key is used instead
+    pub signers: Vec<Pubkey>,
+    /// Number of `signers` required to authorize admin instructions
+    pub threshold: u8,
+    /// Id of the next withdraw proposal
+    pub proposal_count: u64,
+    /// Seconds between queuing a withdrawal or admin change and executing it
+    pub timelock_delay: i64,
+    /// Unix timestamp of the last `ProposeAdmin`
+    pub admin_proposed_at: i64,
+    /// Deposits and withdrawals are rejected while the program is paused
+    pub paused: bool,
+    /// Donors may take their deposits back with `ClaimRefund`
+    pub refunds_enabled: bool,
+    /// Smallest accepted lamports deposit
+    pub min_deposit: u64,
+    /// Largest accepted lamports deposit, zero for no limit
+    pub max_deposit_per_tx: u64,
+    /// Lamports one donor may deposit to a campaign in total, zero for no limit
+    pub max_total_per_donor: u64,
+    /// Access mode of older program versions, unused since the denylist is always
+    /// checked and each campaign decides whether the allowlist is required
+    pub legacy_access_mode: u8,
+    /// Share of every lamports deposit moved to the fee vault, zero disables the fee
+    pub fee_basis_points: u16,
+    /// Account `CollectFees` pays the fee vault balance to
+    pub fee_recipient: Pubkey,
+    /// Campaign that adopted the legacy deposit account, the legacy history is
+    /// migrated into its donor records
+    pub legacy_campaign_id: Option<u64>,
+    /// Multisig change queued by `SetMultisig` while the timelock is enabled
+    pub pending_multisig: Option<PendingMultisig>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct PendingMultisig {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Unix timestamp of the `SetMultisig` that queued the change
+    pub proposed_at: i64,
+}
+
+impl Config {
+    /// Protocol fee of a deposit, rounded down
+    pub fn protocol_fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_basis_points as u128 / TOTAL_BASIS_POINTS as u128) as u64
+    }
+
+    /// Program data account of the program, it stores the upgrade authority
+    pub fn get_program_data_pubkey() -> Pubkey {
+        Pubkey::find_program_address(&[id().as_ref()], &bpf_loader_upgradeable::id()).0
+    }
+
+    /// Whether the timelock started at `since` has passed. A delay that overflows
+    /// the timestamp never expires
+    pub fn is_timelock_expired(&self, since: i64, now: i64) -> bool {
+        matches!(since.checked_add(self.timelock_delay), Some(expires_at) if now >= expires_at)
+    }
+
+    pub fn is_multisig(&self) -> bool {
+        !self.signers.is_empty()
+    }
+
+    /// Keys allowed to vote on withdraw proposals
+    pub fn approvers(&self) -> Vec<Pubkey> {
+        if self.is_multisig() {
+            self.signers.clone()
+        } else {
+            vec![self.admin]
+        }
+    }
+
+    /// Number of approvals required to execute a withdraw proposal
+    pub fn approval_threshold(&self) -> usize {
+        if self.is_multisig() {
+            self.threshold as usize
+        } else {
+            1
+        }
+    }
+
+    pub fn get_config_pubkey_with_bump() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_SEED.as_bytes()], &id())
+    }
+}
+
+impl AccountState for Config {
+    const DISCRIMINATOR: [u8; 8] = *b"config__";
+    const VERSION: u8 = 6;
+    const LEN: usize = 32
+        + 8
+        + (1 + 32)
+        + (4 + 32 * MAX_SIGNERS)
+        + 1
+        + 8
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 1
+        + 2
+        + 32
+        + (1 + 8)
+        + (1 + (4 + 32 * MAX_SIGNERS) + 1 + 8);
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AccessList {
+    Allow,
+    Deny,
+}
+
+/// Presence of the account puts the donor on the list
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct AccessListEntry {
+    pub list: AccessList,
+    pub donor: Pubkey,
+}
+
+impl AccessListEntry {
+    pub fn get_access_list_entry_pubkey_with_bump(
+        list: AccessList,
+        donor: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[ACCESS_LIST_SEED.as_bytes(), &[list as u8], donor.as_ref()],
+            &id(),
+        )
+    }
+}
+
+impl AccountState for AccessListEntry {
+    const DISCRIMINATOR: [u8; 8] = *b"access__";
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 32;
+}
+
+/// Recurring token donation, the PDA is the delegate of the donor's token account
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Subscription {
+    pub campaign_id: u64,
+    pub donor: Pubkey,
+    pub source: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Seconds between collections
+    pub interval: i64,
+    /// Unix timestamp from which the next collection is allowed
+    pub next_collection_at: i64,
+}
+
+impl Subscription {
+    pub fn get_subscription_pubkey_with_bump(
+        campaign_id: u64,
+        mint: &Pubkey,
+        donor: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                SUBSCRIPTION_SEED.as_bytes(),
+                &campaign_id.to_le_bytes(),
+                mint.as_ref(),
+                donor.as_ref(),
+            ],
+            &id(),
+        )
+    }
+
+    pub fn is_due(&self, now: i64) -> bool {
+        now >= self.next_collection_at
+    }
+
+    /// Move the schedule past `now`, missed periods are skipped rather than collected.
+    /// Returns `None` and leaves the schedule as is when it overflows
+    pub fn advance(&mut self, now: i64) -> Option<i64> {
+        let missed = now
+            .checked_sub(self.next_collection_at)?
+            .checked_div(self.interval)?;
+        self.next_collection_at = missed
+            .checked_add(1)?
+            .checked_mul(self.interval)?
+            .checked_add(self.next_collection_at)?;
+        Some(self.next_collection_at)
+    }
+
+    /// Tokens collected by the payments due from `next_collection_at` until `deadline`
+    pub fn allowance(&self, deadline: i64) -> Option<u64> {
+        let payments = deadline
+            .checked_sub(self.next_collection_at)?
+            .checked_div(self.interval)?
+            .checked_add(1)?;
+        self.amount.checked_mul(u64::try_from(payments).ok()?)
+    }
+}
+
+impl AccountState for Subscription {
+    const DISCRIMINATOR: [u8; 8] = *b"subscrip";
+    const VERSION: u8 = 1;
+    const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8;
+}
+
+/// Continuous payout from the deposit account of a campaign to the beneficiary
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Stream {
+    pub campaign_id: u64,
+    pub beneficiary: Pubkey,
+    /// Lamports accrued per second
+    pub rate: u64,
+    pub start: i64,
+    pub end: Option<i64>,
+    /// Unix timestamp up to which the accrued lamports were paid out
+    pub claimed_until: i64,
+}
+
+impl Stream {
+    pub fn get_stream_pubkey_with_bump(campaign_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STREAM_SEED.as_bytes(), &campaign_id.to_le_bytes()], &id())
+    }
+
+    /// Lamports accrued since the last claim
+    pub fn accrued(&self, now: i64) -> u64 {
+        let until = self.end.map_or(now, |end| end.min(now));
+        let seconds = until.saturating_sub(self.claimed_until).max(0) as u64;
+        seconds.saturating_mul(self.rate)
+    }
+
+    /// Pay out whole seconds of the accrued lamports, at most `available`.
+    /// Seconds that could not be paid stay claimable
+    pub fn claim(&mut self, now: i64, available: u64) -> u64 {
+        let seconds = self.accrued(now).min(available) / self.rate;
+        self.claimed_until += seconds as i64;
+        seconds * self.rate
+    }
+}
+
+impl AccountState for Stream {
+    const DISCRIMINATOR: [u8; 8] = *b"stream__";
+    const VERSION: u8 = 1;
+    const LEN: usize = 8 + 32 + 8 + 8 + (1 + 8) + 8;
+}
+
+pub const TOTAL_BASIS_POINTS: u16 = 10_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PayoutShare {
+    pub recipient: Pubkey,
+    pub basis_points: u16,
+}
+
+/// Holds the protocol fees until `CollectFees` pays them to the fee recipient
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct FeeVault {
+    /// Lamports of fees taken since the vault was created
+    pub total_collected: u64,
+}
+
+impl FeeVault {
+    pub fn get_fee_vault_pubkey_with_bump() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FEE_VAULT_SEED.as_bytes()], &id())
+    }
+}
+
+impl AccountState for FeeVault {
+    const DISCRIMINATOR: [u8; 8] = *b"feevault";
+    const VERSION: u8 = 1;
+    const LEN: usize = 8;
+}
+
+/// Receipt tokens minted to donors on lamports deposits.
+/// The PDA is the mint authority of the receipt mint
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct ReceiptConfig {
+    pub mint: Pubkey,
+    /// Receipt token base units minted per deposited lamport, zero disables minting
+    pub rate: u64,
+}
+
+impl ReceiptConfig {
+    pub fn get_receipt_config_pubkey_with_bump() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RECEIPT_SEED.as_bytes()], &id())
+    }
+
+    pub fn get_receipt_mint_pubkey_with_bump() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RECEIPT_MINT_SEED.as_bytes()], &id())
+    }
+
+    /// `None` when the amount doesn't fit into u64
+    pub fn receipt_amount(&self, lamports: u64) -> Option<u64> {
+        lamports.checked_mul(self.rate)
+    }
+}
+
+impl AccountState for ReceiptConfig {
+    const DISCRIMINATOR: [u8; 8] = *b"receipt_";
+    const VERSION: u8 = 1;
+    const LEN: usize = 32 + 8;
+}
+
+/// Recipients the withdrawable balance of a campaign is split between by `Distribute`
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct PayoutTable {
+    pub shares: Vec<PayoutShare>,
+    /// Unix timestamp of the last `SetPayoutTable`, `Distribute` waits for the
+    /// timelock to pass after it
+    pub updated_at: i64,
+}
+
+impl PayoutTable {
+    pub fn get_payout_table_pubkey_with_bump(campaign_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PAYOUT_SEED.as_bytes(), &campaign_id.to_le_bytes()], &id())
+    }
+
+    /// Up to `MAX_PAYOUT_RECIPIENTS` distinct recipients with positive shares
+    /// summing to `TOTAL_BASIS_POINTS`
+    pub fn is_valid(&self) -> bool {
+        let mut recipients: Vec<&Pubkey> = self.shares.iter().map(|s| &s.recipient).collect();
+        recipients.sort();
+        recipients.dedup();
+
+        !self.shares.is_empty()
+            && self.shares.len() <= MAX_PAYOUT_RECIPIENTS
+            && recipients.len() == self.shares.len()
+            && self.shares.iter().all(|s| s.basis_points > 0)
+            && self
+                .shares
+                .iter()
+                .map(|s| s.basis_points as u32)
+                .sum::<u32>()
+                == TOTAL_BASIS_POINTS as u32
+    }
+
+    /// Amount of every recipient in table order. Shares are rounded down and
+    /// the rounding dust goes to the first recipient
+    pub fn split(&self, amount: u64) -> Vec<u64> {
+        let mut amounts: Vec<u64> = self
+            .shares
+            .iter()
+            .map(|s| (amount as u128 * s.basis_points as u128 / TOTAL_BASIS_POINTS as u128) as u64)
+            .collect();
+        let dust = amount - amounts.iter().sum::<u64>();
+        if let Some(first) = amounts.first_mut() {
+            *first += dust;
+        }
+        amounts
+    }
+}
+
+impl AccountState for PayoutTable {
+    const DISCRIMINATOR: [u8; 8] = *b"payout__";
+    const VERSION: u8 = 2;
+    const LEN: usize = 4 + (32 + 2) * MAX_PAYOUT_RECIPIENTS + 8;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ProposalStatus {
+    Active,
+    Executed,
+    Rejected,
+    Cancelled,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct WithdrawProposal {
+    pub id: u64,
+    pub campaign_id: u64,
+    pub proposer: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub memo: String,
+    pub approvals: Vec<Pubkey>,
+    pub rejections: Vec<Pubkey>,
+    pub status: ProposalStatus,
+    /// Unix timestamp when the proposal reached the approval threshold
+    pub queued_at: Option<i64>,
+    /// Mint of the proposed tokens, lamports are proposed when `None`
+    pub mint: Option<Pubkey>,
+}
+
+impl WithdrawProposal {
+    pub fn get_proposal_pubkey_with_bump(proposal_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[PROPOSAL_SEED.as_bytes(), &proposal_id.to_le_bytes()],
+            &id(),
+        )
+    }
+
+    /// Approvals given by keys which are still approvers
+    pub fn approvals_count(&self, config: &Config) -> usize {
+        let approvers = config.approvers();
+        self.approvals
+            .iter()
+            .filter(|approval| approvers.contains(approval))
+            .count()
+    }
+
+    /// Rejections given by keys which are still approvers
+    pub fn rejections_count(&self, config: &Config) -> usize {
+        let approvers = config.approvers();
+        self.rejections
+            .iter()
+            .filter(|rejection| approvers.contains(rejection))
+            .count()
+    }
+}
+
+impl AccountState for WithdrawProposal {
+    const DISCRIMINATOR: [u8; 8] = *b"proposal";
+    const VERSION: u8 = 2;
+    const LEN: usize = 8
+        + 8
+        + 32
+        + 8
+        + 32
+        + (4 + MAX_MEMO_LEN)
+        + 2 * (4 + 32 * MAX_SIGNERS)
+        + 1
+        + (1 + 8)
+        + (1 + 32);
 }
 
 #[cfg(test)]
 mod test {
     use borsh::BorshSerialize;
 
-    use crate::pubkey;
     use crate::state::*;
+    use solana_program::pubkey;
 
     #[test]
     fn test_serialization() {
@@ -69,8 +854,236 @@ mod test {
     }
 
     #[test]
-    fn test_get_pda_address_with_seed() {
-        let (address, bump) = DepositHistoryData::get_pda_pubkey_with_bump();
+    fn test_history_zero_copy_remove() {
+        let donors = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut history = DepositHistoryData {
+            history: HashMap::new(),
+        };
+        history.history.insert(donors[0].to_bytes(), 10);
+        history.history.insert(donors[1].to_bytes(), 20);
+        history.history.insert(donors[2].to_bytes(), 30);
+        let mut data = history.try_to_vec().unwrap();
+        data.resize(60 * 100, 0);
+
+        assert_eq!(DepositHistoryData::entries_count(&data), Ok(3));
+        assert_eq!(
+            DepositHistoryData::remove_amount(&mut data, &donors[1]),
+            Ok(Some(20))
+        );
+        assert_eq!(
+            DepositHistoryData::remove_amount(&mut data, &donors[1]),
+            Ok(None)
+        );
+        assert_eq!(DepositHistoryData::entries_count(&data), Ok(2));
+
+        history.history.remove(&donors[1].to_bytes());
+        assert_eq!(
+            DepositHistoryData::deserialize(&mut &data[..]).unwrap(),
+            history
+        );
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let config = Config {
+            admin: pubkey!("GizgqMPamZ5joAZ8XxLPqshwvqD8xDFCp1buwhbi28sp"),
+            withdraw_threshold: 100,
+            pending_admin: Some(pubkey!("3N7dHiEv6fz59uwNBTMNp9Fei9JKWL6je1fUnDxWXdbQ")),
+            signers: vec![Pubkey::new_unique(); MAX_SIGNERS],
+            threshold: 2,
+            proposal_count: 3,
+            timelock_delay: 86400,
+            admin_proposed_at: 1650000000,
+            paused: true,
+            refunds_enabled: true,
+            min_deposit: 1,
+            max_deposit_per_tx: 2,
+            max_total_per_donor: 3,
+            legacy_access_mode: 2,
+            fee_basis_points: 250,
+            fee_recipient: Pubkey::new_unique(),
+            legacy_campaign_id: Some(1),
+            pending_multisig: Some(PendingMultisig {
+                signers: vec![Pubkey::new_unique(); MAX_SIGNERS],
+                threshold: 3,
+                proposed_at: 1650000000,
+            }),
+        };
+
+        let serialized_data = config.try_to_vec().unwrap();
+        assert_eq!(serialized_data.len(), Config::LEN);
+        assert_eq!(Config::try_from_slice(&serialized_data).unwrap(), config);
+    }
+
+    #[test]
+    fn test_outdated_config() {
+        let config = Config {
+            admin: Pubkey::new_unique(),
+            withdraw_threshold: 100,
+            pending_admin: None,
+            signers: vec![],
+            threshold: 0,
+            proposal_count: 0,
+            timelock_delay: 0,
+            admin_proposed_at: 0,
+            paused: false,
+            refunds_enabled: false,
+            min_deposit: 0,
+            max_deposit_per_tx: 0,
+            max_total_per_donor: 0,
+            legacy_access_mode: 0,
+            fee_basis_points: 0,
+            fee_recipient: Pubkey::default(),
+            legacy_campaign_id: None,
+            pending_multisig: None,
+        };
+
+        // Version 1 config accounts have no deposit limits, access mode, protocol fee
+        // and legacy campaign
+        let mut data = vec![0; Config::SPACE];
+        config.pack(&mut data).unwrap();
+        data[8] = 1;
+        data.truncate(
+            ACCOUNT_HEADER_LEN + config.try_to_vec().unwrap().len() - 3 * 8 - 1 - 2 - 32 - 1,
+        );
+        assert_eq!(
+            Config::unpack(&data),
+            Err(DonationError::UnsupportedAccountVersion.into())
+        );
+        assert!(!Config::is_current(&data));
+        assert_eq!(Config::unpack_outdated(&data).unwrap(), config);
+    }
+
+    #[test]
+    fn test_donor_record_serialization() {
+        let record = DonorRecord {
+            donor: Pubkey::new_unique(),
+            amount: 100,
+            receipts: 200,
+            gross_amount: 101,
+            unburned_receipts: 50,
+        };
+
+        let serialized_data = record.try_to_vec().unwrap();
+        assert_eq!(serialized_data.len(), DonorRecord::LEN);
+        assert_eq!(
+            DonorRecord::try_from_slice(&serialized_data).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_account_header() {
+        let record = DonorRecord {
+            donor: Pubkey::new_unique(),
+            amount: 100,
+            receipts: 200,
+            gross_amount: 101,
+            unburned_receipts: 50,
+        };
+
+        let mut data = vec![0; DonorRecord::SPACE];
+        record.pack(&mut data).unwrap();
+        assert_eq!(
+            data[..9],
+            [b'd', b'o', b'n', b'o', b'r', b'r', b'e', b'c', 4]
+        );
+        assert_eq!(DonorRecord::unpack(&data).unwrap(), record);
+        assert_eq!(
+            DepositTotals::unpack(&data),
+            Err(DonationError::InvalidAccountDiscriminator.into())
+        );
+
+        data[8] = 5;
+        assert_eq!(
+            DonorRecord::unpack(&data),
+            Err(DonationError::UnsupportedAccountVersion.into())
+        );
+    }
+
+    #[test]
+    fn test_unversioned_campaign() {
+        // Campaign accounts created before `donors_count` was added
+        let data = [[1u8; 8], [2; 8], [3; 8], [4; 8]].concat();
+        let campaign = Campaign::unpack_outdated(&data).unwrap();
+        assert_eq!(campaign.total_raised, u64::from_le_bytes([4; 8]));
+        assert_eq!(campaign.donors_count, 0);
+        assert!(!Campaign::is_current(&data));
+    }
+
+    #[test]
+    fn test_campaign_serialization() {
+        let campaign = Campaign {
+            id: 1,
+            goal_lamports: 100,
+            deadline: 1650000000,
+            total_raised: 50,
+            donors_count: 2,
+            total_fees: 1,
+            token_mints: 0,
+            allowlist_required: false,
+            open_proposals: 0,
+        };
+
+        let serialized_data = campaign.try_to_vec().unwrap();
+        assert_eq!(serialized_data.len(), Campaign::LEN);
+        assert_eq!(
+            Campaign::try_from_slice(&serialized_data).unwrap(),
+            campaign
+        );
+    }
+
+    #[test]
+    fn test_campaign_is_failed() {
+        let mut campaign = Campaign {
+            id: 1,
+            goal_lamports: 100,
+            deadline: 1650000000,
+            total_raised: 50,
+            donors_count: 2,
+            total_fees: 1,
+            token_mints: 0,
+            allowlist_required: false,
+            open_proposals: 0,
+        };
+        assert!(!campaign.is_failed(1650000000));
+        assert!(campaign.is_failed(1650000001));
+
+        campaign.total_raised = 100;
+        assert!(!campaign.is_failed(1650000001));
+    }
+
+    #[test]
+    fn test_proposal_serialization() {
+        let proposal = WithdrawProposal {
+            id: 1,
+            campaign_id: 2,
+            proposer: Pubkey::new_unique(),
+            amount: 100,
+            destination: Pubkey::new_unique(),
+            memo: "a".repeat(MAX_MEMO_LEN),
+            approvals: vec![Pubkey::new_unique(); MAX_SIGNERS],
+            rejections: vec![Pubkey::new_unique(); MAX_SIGNERS],
+            status: ProposalStatus::Executed,
+            queued_at: Some(1650000000),
+            mint: Some(Pubkey::new_unique()),
+        };
+
+        let serialized_data = proposal.try_to_vec().unwrap();
+        assert_eq!(serialized_data.len(), WithdrawProposal::LEN);
+        assert_eq!(
+            WithdrawProposal::try_from_slice(&serialized_data).unwrap(),
+            proposal
+        );
+    }
+
+    #[test]
+    fn test_get_legacy_pda_address_with_seed() {
+        let (address, bump) = DepositHistoryData::get_legacy_pda_pubkey_with_bump();
         assert_eq!(
             address,
             pubkey!("7jYpqqFSVDCGwTigh8a2vkcUfZKHGsrSVXAsfj8GeS7j")
@@ -80,11 +1093,142 @@ mod test {
 
     #[test]
     fn test_get_deposit_address_with_seed() {
-        let (address, bump) = DepositHistoryData::get_deposit_with_bump();
+        let (address, bump) = DepositHistoryData::get_deposit_with_bump(1);
         assert_eq!(
             address,
-            pubkey!("9Ry9NaGh9kKrBSxyUMWTsgRDVDfocUrDvMoZCbvh9LxC")
+            pubkey!("6MXzQH7VX9oRTEzwGMRot7ZyzoJNVuUPRLptewsdghKo")
         );
         assert_eq!(bump, 255);
     }
+
+    #[test]
+    fn test_get_config_address_with_seed() {
+        let (address, bump) = Config::get_config_pubkey_with_bump();
+        assert_eq!(
+            address,
+            pubkey!("3NULAcjpGM3pE5Pu793XZnDDPA2DeD7NyAyXYNzSAtDf")
+        );
+        assert_eq!(bump, 253);
+    }
+
+    #[test]
+    fn test_subscription_schedule() {
+        let mut subscription = Subscription {
+            campaign_id: 1,
+            donor: Pubkey::new_unique(),
+            source: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 10,
+            interval: 100,
+            next_collection_at: 1000,
+        };
+        assert!(!subscription.is_due(999));
+        assert!(subscription.is_due(1000));
+
+        assert_eq!(subscription.allowance(1000), Some(10));
+        assert_eq!(subscription.allowance(1250), Some(30));
+
+        assert_eq!(subscription.advance(1000), Some(1100));
+        assert_eq!(subscription.next_collection_at, 1100);
+
+        // Two missed periods are not collected later
+        assert_eq!(subscription.advance(1350), Some(1400));
+        assert_eq!(subscription.next_collection_at, 1400);
+
+        subscription.interval = i64::MAX;
+        assert_eq!(subscription.advance(1400), None);
+        assert_eq!(subscription.next_collection_at, 1400);
+        assert_eq!(subscription.allowance(i64::MIN), None);
+
+        subscription.interval = 1;
+        subscription.amount = u64::MAX;
+        assert_eq!(subscription.allowance(1401), None);
+    }
+
+    #[test]
+    fn test_stream_claim() {
+        let mut stream = Stream {
+            campaign_id: 1,
+            beneficiary: Pubkey::new_unique(),
+            rate: 10,
+            start: 1000,
+            end: Some(1100),
+            claimed_until: 1000,
+        };
+        assert_eq!(stream.accrued(900), 0);
+        assert_eq!(stream.accrued(1050), 500);
+
+        // Only whole seconds fitting the balance are paid
+        assert_eq!(stream.claim(1050, 255), 250);
+        assert_eq!(stream.claimed_until, 1025);
+
+        // Nothing accrues after the end
+        assert_eq!(stream.claim(2000, u64::MAX), 750);
+        assert_eq!(stream.claimed_until, 1100);
+        assert_eq!(stream.accrued(3000), 0);
+    }
+
+    #[test]
+    fn test_protocol_fee() {
+        let mut config = Config::unpack_outdated(&[]).unwrap();
+        assert_eq!(config.protocol_fee(1000), 0);
+
+        config.fee_basis_points = 250;
+        assert_eq!(config.protocol_fee(1000), 25);
+        assert_eq!(config.protocol_fee(39), 0);
+        assert_eq!(config.protocol_fee(u64::MAX), u64::MAX / 40);
+    }
+
+    #[test]
+    fn test_timelock_expired() {
+        let mut config = Config::unpack_outdated(&[]).unwrap();
+        config.timelock_delay = 3600;
+        assert!(!config.is_timelock_expired(1000, 4599));
+        assert!(config.is_timelock_expired(1000, 4600));
+
+        config.timelock_delay = i64::MAX;
+        assert!(!config.is_timelock_expired(1000, i64::MAX));
+    }
+
+    #[test]
+    fn test_receipt_amount() {
+        let mut receipt_config = ReceiptConfig {
+            mint: Pubkey::new_unique(),
+            rate: 0,
+        };
+        assert_eq!(receipt_config.receipt_amount(1000), Some(0));
+
+        receipt_config.rate = 3;
+        assert_eq!(receipt_config.receipt_amount(1000), Some(3000));
+        assert_eq!(receipt_config.receipt_amount(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_payout_split() {
+        let share = |basis_points| PayoutShare {
+            recipient: Pubkey::new_unique(),
+            basis_points,
+        };
+        let table = PayoutTable {
+            shares: vec![share(3334), share(3333), share(3333)],
+            updated_at: 0,
+        };
+        assert!(table.is_valid());
+        assert_eq!(table.split(100), [34, 33, 33]);
+        assert_eq!(table.split(10), [4, 3, 3]);
+        assert_eq!(table.split(1), [1, 0, 0]);
+
+        let table = PayoutTable {
+            shares: vec![share(5000), share(4999)],
+            updated_at: 0,
+        };
+        assert!(!table.is_valid());
+
+        let duplicate = share(5000);
+        let table = PayoutTable {
+            shares: vec![duplicate, duplicate],
+            updated_at: 0,
+        };
+        assert!(!table.is_valid());
+    }
 }
@@ -3,33 +3,72 @@
 use std::collections::HashMap;
 
 use borsh::BorshSerialize;
+use solana_program::clock::Clock;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::native_token::sol_to_lamports;
 use solana_program::{system_instruction, sysvar};
 use solana_program_test::{processor, tokio, ProgramTest, ProgramTestContext};
 use solana_sdk::account::{Account, WritableAccount};
+use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config;
+use spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use spl_token_2022::state::Mint;
 
 use solana_test_task::entrypoint::process_instruction;
-use solana_test_task::id;
 use solana_test_task::instruction::DepositInstructions;
-use solana_test_task::state::DepositHistoryData;
+use solana_test_task::state::{
+    AccessList, AccountKind, AccountState, Campaign, Config, DepositAccount, DepositHistoryData,
+    DepositTotals, DonorRecord, FeeVault, PayoutShare, PayoutTable, ProposalStatus, ReceiptConfig,
+    Stream, Subscription, WithdrawProposal,
+};
+use solana_test_task::{id, MAX_TIMELOCK_DELAY};
+
+const CAMPAIGN_ID: u64 = 1;
+const CAMPAIGN_DURATION: i64 = 86400;
+
+/// Program data account of the upgradeable loader, with the given upgrade authority
+fn program_data_account(authority: &Pubkey) -> Account {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: solana_sdk::bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
 
 struct Env {
     ctx: ProgramTestContext,
     admin: Keypair,
     user: Keypair,
+    deadline: i64,
 }
 
 impl Env {
     async fn new() -> Self {
-        let program_test =
+        let mut program_test =
             ProgramTest::new("solana_test_task", id(), processor!(process_instruction));
-        let mut ctx = program_test.start_with_context().await;
-
+        program_test.add_program(
+            "spl_token_2022",
+            spl_token_2022::id(),
+            processor!(spl_token_2022::processor::Processor::process),
+        );
+        program_test.add_program(
+            "spl_associated_token_account",
+            spl_associated_token_account::id(),
+            processor!(spl_associated_token_account::processor::process_instruction),
+        );
         let admin = Keypair::from_bytes(&[
             203, 219, 86, 187, 107, 81, 112, 226, 4, 227, 158, 252, 76, 123, 149, 180, 95, 198, 36,
             9, 235, 156, 55, 45, 74, 84, 77, 104, 33, 95, 92, 16, 35, 32, 15, 255, 219, 159, 176,
@@ -37,6 +76,11 @@ impl Env {
             1, 39, 21, 32, 244, 59,
         ])
         .unwrap();
+        program_test.add_account(
+            Config::get_program_data_pubkey(),
+            program_data_account(&admin.pubkey()),
+        );
+        let mut ctx = program_test.start_with_context().await;
         let user = Keypair::new();
 
         // credit admin and user accounts
@@ -61,33 +105,101 @@ impl Env {
             .await
             .unwrap();
 
-        // init donation account
+        // init config and donation accounts
+        let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+        let deadline = clock.unix_timestamp + CAMPAIGN_DURATION;
         let tx = Transaction::new_signed_with_payer(
-            &[DepositInstructions::create_initialize()],
+            &[
+                DepositInstructions::create_initialize_config(
+                    &admin.pubkey(),
+                    sol_to_lamports(0.01),
+                ),
+                DepositInstructions::create_initialize(
+                    &admin.pubkey(),
+                    CAMPAIGN_ID,
+                    sol_to_lamports(1f64),
+                    deadline,
+                ),
+            ],
             Some(&admin.pubkey()),
             &[&admin],
             ctx.last_blockhash,
         );
         ctx.banks_client.process_transaction(tx).await.unwrap();
 
-        Env { ctx, admin, user }
+        Env {
+            ctx,
+            admin,
+            user,
+            deadline,
+        }
     }
 }
 
+/// Account data with the discriminator and version header
+fn packed<T: AccountState>(state: &T) -> Vec<u8> {
+    let mut data = vec![0; T::SPACE];
+    state.pack(&mut data).unwrap();
+    data
+}
+
 #[tokio::test]
 async fn test_setup() {
     let mut env: Env = Env::new().await;
-    let (pda_pubkey, _) = DepositHistoryData::get_pda_pubkey_with_bump();
-    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    let config = Config {
+        admin: env.admin.pubkey(),
+        withdraw_threshold: sol_to_lamports(0.01),
+        pending_admin: None,
+        signers: vec![],
+        threshold: 0,
+        proposal_count: 0,
+        timelock_delay: 0,
+        admin_proposed_at: 0,
+        paused: false,
+        refunds_enabled: false,
+        min_deposit: 0,
+        max_deposit_per_tx: 0,
+        max_total_per_donor: 0,
+        legacy_access_mode: 0,
+        fee_basis_points: 0,
+        fee_recipient: Pubkey::default(),
+        legacy_campaign_id: None,
+        pending_multisig: None,
+    };
+    let data = packed(&config);
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(config_pubkey)
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::create(6702480, data, id(), false, 0,)
+    );
 
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let campaign = Campaign {
+        id: CAMPAIGN_ID,
+        goal_lamports: sol_to_lamports(1f64),
+        deadline: env.deadline,
+        total_raised: 0,
+        donors_count: 0,
+        total_fees: 0,
+        token_mints: 0,
+        allowlist_required: false,
+        open_proposals: 0,
+    };
     assert_eq!(
         env.ctx
             .banks_client
-            .get_account(pubkey!(pda_pubkey))
+            .get_account(campaign_pubkey)
             .await
             .unwrap()
             .unwrap(),
-        Account::new(42650880, 60 * 100, &id(),)
+        Account::create(1405920, packed(&campaign), id(), false, 0,)
     );
 
     assert_eq!(
@@ -97,19 +209,46 @@ async fn test_setup() {
             .await
             .unwrap()
             .unwrap(),
-        Account::new(890880, 0, &id(),)
+        Account::create(953520, packed(&DepositAccount {}), id(), false, 0,)
+    );
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(0)))"
+)]
+async fn test_only_upgrade_authority_can_initialize_config() {
+    let mut program_test =
+        ProgramTest::new("solana_test_task", id(), processor!(process_instruction));
+    program_test.add_account(
+        Config::get_program_data_pubkey(),
+        program_data_account(&Pubkey::new_unique()),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_initialize_config(
+            &ctx.payer.pubkey(),
+            sol_to_lamports(0.01),
+        )],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
     );
+    ctx.banks_client.process_transaction(tx).await.unwrap();
 }
 
 #[tokio::test]
 async fn test_make_donation() {
     let mut env: Env = Env::new().await;
-    let (pda_pubkey, _) = DepositHistoryData::get_pda_pubkey_with_bump();
-    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
 
     let tx = Transaction::new_signed_with_payer(
         &[DepositInstructions::create_deposit(
             &env.user.pubkey(),
+            CAMPAIGN_ID,
             sol_to_lamports(0.01),
         )],
         Some(&env.user.pubkey()),
@@ -125,38 +264,55 @@ async fn test_make_donation() {
             .await
             .unwrap()
             .unwrap(),
-        Account::new(
-            10890880,
+        Account::create(
+            10953520,
+            packed(&DepositAccount {}),
+            pubkey!("3jYkeV2vknPL5UgFxANiNBUnRJuGeZcBP22C2gZJ1BT7"),
+            false,
             0,
-            &pubkey!("3jYkeV2vknPL5UgFxANiNBUnRJuGeZcBP22C2gZJ1BT7"),
         )
     );
 
-    let mut history = HashMap::new();
-    history.insert(env.user.pubkey().to_bytes(), sol_to_lamports(0.01));
-
-    let deposit_history_data = DepositHistoryData { history };
-    let mut data = deposit_history_data.try_to_vec().unwrap();
-    data.resize(6000, 0);
-
+    let record = DonorRecord {
+        donor: env.user.pubkey(),
+        amount: sol_to_lamports(0.01),
+        receipts: 0,
+        gross_amount: sol_to_lamports(0.01),
+        unburned_receipts: 0,
+    };
     assert_eq!(
         env.ctx
             .banks_client
-            .get_account(pubkey!(pda_pubkey))
+            .get_account(record_pubkey)
             .await
             .unwrap()
             .unwrap(),
-        Account::create(42650880, data, id(), false, 0,)
+        Account::create(1398960, packed(&record), id(), false, 0,)
     );
+
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let campaign_account = env
+        .ctx
+        .banks_client
+        .get_account(campaign_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let campaign = Campaign::unpack(&campaign_account.data).unwrap();
+    assert_eq!(campaign.total_raised, sol_to_lamports(0.01));
+    assert_eq!(campaign.donors_count, 1);
 }
 
 #[tokio::test]
 async fn test_withdraw() {
     let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
 
     let tx = Transaction::new_signed_with_payer(
         &[DepositInstructions::create_deposit(
             &env.user.pubkey(),
+            CAMPAIGN_ID,
             sol_to_lamports(2f64),
         )],
         Some(&env.user.pubkey()),
@@ -166,7 +322,12 @@ async fn test_withdraw() {
     env.ctx.banks_client.process_transaction(tx).await.unwrap();
 
     let tx = Transaction::new_signed_with_payer(
-        &[DepositInstructions::create_withdraw()],
+        &[DepositInstructions::create_withdraw(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &vendor.pubkey(),
+            sol_to_lamports(1f64),
+        )],
         Some(&env.admin.pubkey()),
         &[&env.admin],
         env.ctx.last_blockhash,
@@ -176,12 +337,109 @@ async fn test_withdraw() {
     assert_eq!(
         env.ctx
             .banks_client
-            .get_account(env.admin.pubkey())
+            .get_account(vendor.pubkey())
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::new(
+            sol_to_lamports(1f64),
+            0,
+            &pubkey!("11111111111111111111111111111111"),
+        )
+    );
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(deposit_pubkey)
             .await
             .unwrap()
             .unwrap(),
-        Account::new(6956448240, 0, &pubkey!("11111111111111111111111111111111"),)
+        Account::create(1000953520, packed(&DepositAccount {}), id(), false, 0,)
+    );
+}
+
+#[tokio::test]
+async fn test_campaigns_are_separate() {
+    let mut env: Env = Env::new().await;
+    let other_campaign_id = CAMPAIGN_ID + 1;
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+    let (other_deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(other_campaign_id);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize(
+                &env.admin.pubkey(),
+                other_campaign_id,
+                sol_to_lamports(1f64),
+                env.deadline,
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                other_campaign_id,
+                sol_to_lamports(1f64),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(deposit_pubkey)
+            .await
+            .unwrap(),
+        2000953520
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(other_deposit_pubkey)
+            .await
+            .unwrap(),
+        1000953520
+    );
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(1)))"
+)]
+async fn test_cant_withdraw_more_than_deposited() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_deposit(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            sol_to_lamports(2f64),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_withdraw(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &env.admin.pubkey(),
+            sol_to_lamports(2f64) + 1,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
     );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
 }
 
 #[tokio::test]
@@ -194,6 +452,7 @@ async fn test_user_cant_withdraw() {
     let tx = Transaction::new_signed_with_payer(
         &[DepositInstructions::create_deposit(
             &env.user.pubkey(),
+            CAMPAIGN_ID,
             sol_to_lamports(2f64),
         )],
         Some(&env.user.pubkey()),
@@ -202,14 +461,22 @@ async fn test_user_cant_withdraw() {
     );
     env.ctx.banks_client.process_transaction(tx).await.unwrap();
 
-    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump();
+    let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
     let tx = Transaction::new_signed_with_payer(
         &[Instruction::new_with_borsh(
             id(),
-            &DepositInstructions::Withdraw,
+            &DepositInstructions::Withdraw {
+                campaign_id: CAMPAIGN_ID,
+                amount: sol_to_lamports(1f64),
+            },
             vec![
                 AccountMeta::new(env.user.pubkey(), true),
+                AccountMeta::new_readonly(config_pubkey, false),
+                AccountMeta::new_readonly(campaign_pubkey, false),
                 AccountMeta::new(deposit_pubkey, false),
+                AccountMeta::new(env.user.pubkey(), false),
                 AccountMeta::new_readonly(sysvar::rent::id(), false),
             ],
         )],
@@ -219,3 +486,2865 @@ async fn test_user_cant_withdraw() {
     );
     env.ctx.banks_client.process_transaction(tx).await.unwrap();
 }
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(0)))"
+)]
+async fn test_previous_admin_cant_withdraw() {
+    let mut env: Env = Env::new().await;
+    let new_admin = Keypair::new();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_propose_admin(&env.admin.pubkey(), &new_admin.pubkey()),
+            DepositInstructions::create_accept_admin(&new_admin.pubkey()),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin, &new_admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+    let config_account = env
+        .ctx
+        .banks_client
+        .get_account(config_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = Config::unpack(&config_account.data).unwrap();
+    assert_eq!(config.admin, new_admin.pubkey());
+    assert_eq!(config.pending_admin, None);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_withdraw(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &env.admin.pubkey(),
+            sol_to_lamports(1f64),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(3)))"
+)]
+async fn test_only_pending_admin_can_accept() {
+    let mut env: Env = Env::new().await;
+    let new_admin = Keypair::new();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_propose_admin(&env.admin.pubkey(), &new_admin.pubkey()),
+            DepositInstructions::create_accept_admin(&env.user.pubkey()),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn enable_multisig(env: &mut Env, signers: &[&Keypair], threshold: u8) {
+    let signer_pubkeys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_set_multisig(
+                &env.admin.pubkey(),
+                &signer_pubkeys,
+                threshold,
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_multisig_withdraw() {
+    let mut env: Env = Env::new().await;
+    let (alice, bob, carol) = (Keypair::new(), Keypair::new(), Keypair::new());
+    enable_multisig(&mut env, &[&alice, &bob, &carol], 2).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::with_signers(
+            DepositInstructions::create_withdraw(
+                &alice.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+                sol_to_lamports(1f64),
+            ),
+            &[carol.pubkey()],
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user, &alice, &carol],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(4)))"
+)]
+async fn test_multisig_withdraw_below_threshold() {
+    let mut env: Env = Env::new().await;
+    let (alice, bob, carol) = (Keypair::new(), Keypair::new(), Keypair::new());
+    enable_multisig(&mut env, &[&alice, &bob, &carol], 2).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::with_signers(
+            DepositInstructions::create_withdraw(
+                &alice.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+                sol_to_lamports(1f64),
+            ),
+            &[alice.pubkey()],
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user, &alice],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn create_proposal(env: &mut Env, proposer: &Keypair, destination: &Pubkey) {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(
+                &env.user.pubkey(),
+                &proposer.pubkey(),
+                sol_to_lamports(1f64),
+            ),
+            DepositInstructions::create_withdraw_proposal(
+                &proposer.pubkey(),
+                0,
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+                destination,
+                "vendor invoice #1",
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, proposer],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_proposal_withdraw() {
+    let mut env: Env = Env::new().await;
+    let (alice, bob, carol) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let vendor = Keypair::new();
+    enable_multisig(&mut env, &[&alice, &bob, &carol], 2).await;
+    create_proposal(&mut env, &alice, &vendor.pubkey()).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_approve_proposal(
+            &bob.pubkey(),
+            0,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user, &bob],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_proposal(
+            0,
+            CAMPAIGN_ID,
+            &vendor.pubkey(),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(vendor.pubkey())
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::new(
+            sol_to_lamports(1f64),
+            0,
+            &pubkey!("11111111111111111111111111111111"),
+        )
+    );
+
+    let (proposal_pubkey, _) = WithdrawProposal::get_proposal_pubkey_with_bump(0);
+    let proposal_account = env
+        .ctx
+        .banks_client
+        .get_account(proposal_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let proposal = WithdrawProposal::unpack(&proposal_account.data).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+    assert_eq!(proposal.approvals, vec![alice.pubkey(), bob.pubkey()]);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(9)))"
+)]
+async fn test_cant_execute_proposal_without_approvals() {
+    let mut env: Env = Env::new().await;
+    let (alice, bob, carol) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let vendor = Keypair::new();
+    enable_multisig(&mut env, &[&alice, &bob, &carol], 2).await;
+    create_proposal(&mut env, &alice, &vendor.pubkey()).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_proposal(
+            0,
+            CAMPAIGN_ID,
+            &vendor.pubkey(),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn enable_timelock(env: &mut Env, delay: i64) {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_set_timelock(&env.admin.pubkey(), delay),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(12)))"
+)]
+async fn test_cant_withdraw_directly_with_timelock() {
+    let mut env: Env = Env::new().await;
+    enable_timelock(&mut env, 3600).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_withdraw(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &env.admin.pubkey(),
+            sol_to_lamports(1f64),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timelocked_multisig_change() {
+    let mut env: Env = Env::new().await;
+    enable_timelock(&mut env, 3600).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_multisig(&env.admin.pubkey(), &[env.user.pubkey()], 1),
+            DepositInstructions::create_apply_multisig(),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(11))
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_set_multisig(
+            &env.admin.pubkey(),
+            &[env.user.pubkey()],
+            1,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3600;
+    env.ctx.set_sysvar(&clock);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_apply_multisig()],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(config_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = Config::unpack(&account.data).unwrap();
+    assert_eq!(config.signers, vec![env.user.pubkey()]);
+    assert_eq!(config.threshold, 1);
+    assert_eq!(config.pending_multisig, None);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(37)))"
+)]
+async fn test_cant_start_stream_before_timelock() {
+    let mut env: Env = Env::new().await;
+    let beneficiary = Keypair::new();
+    enable_timelock(&mut env, 3600).await;
+    let clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_stream(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &beneficiary.pubkey(),
+            sol_to_lamports(0.01),
+            clock.unix_timestamp + 3599,
+            None,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timelocked_proposal_withdraw() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+    enable_timelock(&mut env, 3600).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_withdraw_proposal(
+            &env.admin.pubkey(),
+            0,
+            CAMPAIGN_ID,
+            sol_to_lamports(1f64),
+            &vendor.pubkey(),
+            "vendor invoice #1",
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_proposal(
+            0,
+            CAMPAIGN_ID,
+            &vendor.pubkey(),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(11))
+    );
+
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3600;
+    env.ctx.set_sysvar(&clock);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_proposal(
+            0,
+            CAMPAIGN_ID,
+            &vendor.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(vendor.pubkey())
+            .await
+            .unwrap(),
+        sol_to_lamports(1f64)
+    );
+}
+
+#[tokio::test]
+async fn test_cant_bypass_timelock_with_huge_delay() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+    enable_timelock(&mut env, MAX_TIMELOCK_DELAY).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_set_timelock(
+            &env.admin.pubkey(),
+            i64::MAX,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(13))
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_withdraw_proposal(
+                &env.admin.pubkey(),
+                0,
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+                &vendor.pubkey(),
+                "vendor invoice #1",
+            ),
+            DepositInstructions::create_execute_proposal(0, CAMPAIGN_ID, &vendor.pubkey()),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(11))
+    );
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(14)))"
+)]
+async fn test_cant_deposit_while_paused() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_pause(&env.admin.pubkey()),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_deposit_after_unpause() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_pause(&env.admin.pubkey()),
+            DepositInstructions::create_unpause(&env.admin.pubkey()),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_refund() {
+    let mut env: Env = Env::new().await;
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_refund(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.user.pubkey(),
+                sol_to_lamports(0.5),
+            ),
+            DepositInstructions::create_set_refunds_enabled(&env.admin.pubkey(), true),
+            DepositInstructions::create_claim_refund(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(env.user.pubkey())
+            .await
+            .unwrap(),
+        sol_to_lamports(4f64)
+    );
+
+    let record_account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let record = DonorRecord::unpack(&record_account.data).unwrap();
+    assert_eq!(record.amount, sol_to_lamports(1f64));
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(16)))"
+)]
+async fn test_cant_claim_refund_when_disabled() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_claim_refund(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(15)))"
+)]
+async fn test_cant_refund_more_than_deposited() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+            DepositInstructions::create_refund(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.user.pubkey(),
+                sol_to_lamports(2f64),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.user, &env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn pass_deadline(env: &mut Env) {
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = env.deadline + 1;
+    env.ctx.set_sysvar(&clock);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(19)))"
+)]
+async fn test_cant_withdraw_before_goal() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+            DepositInstructions::create_withdraw(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &vendor.pubkey(),
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(18)))"
+)]
+async fn test_cant_deposit_after_deadline() {
+    let mut env: Env = Env::new().await;
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_deposit(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            sol_to_lamports(1f64),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reclaim_failed_campaign() {
+    let mut env: Env = Env::new().await;
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_deposit(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            sol_to_lamports(0.5),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_reclaim(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(deposit_pubkey)
+            .await
+            .unwrap(),
+        953520
+    );
+
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(campaign_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let campaign = Campaign::unpack(&account.data).unwrap();
+    assert_eq!(campaign.total_raised, 0);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(20)))"
+)]
+async fn test_cant_reclaim_when_goal_reached() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_deposit(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            sol_to_lamports(1f64),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_reclaim(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn create_mint(
+    env: &mut Env,
+    token_program: &Pubkey,
+    transfer_fee_bps: Option<u16>,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = env.ctx.banks_client.get_rent().await.unwrap();
+    let extensions = match transfer_fee_bps {
+        Some(_) => vec![ExtensionType::TransferFeeConfig],
+        None => vec![],
+    };
+    let space = ExtensionType::get_account_len::<Mint>(&extensions);
+
+    let mut instructions = vec![system_instruction::create_account(
+        &env.admin.pubkey(),
+        &mint.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        token_program,
+    )];
+    if let Some(transfer_fee_bps) = transfer_fee_bps {
+        instructions.push(
+            initialize_transfer_fee_config(
+                token_program,
+                &mint.pubkey(),
+                None,
+                None,
+                transfer_fee_bps,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint(
+            token_program,
+            &mint.pubkey(),
+            &env.admin.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &mint],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_account(
+    env: &mut Env,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = get_associated_token_address_with_program_id(owner, mint, token_program);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(&env.admin.pubkey(), owner, mint, token_program),
+            spl_token_2022::instruction::mint_to(
+                token_program,
+                mint,
+                &token_account,
+                &env.admin.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+    token_account
+}
+
+async fn token_balance(env: &mut Env, token_account: &Pubkey) -> u64 {
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(*token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+async fn token_record(env: &mut Env, mint: &Pubkey) -> DonorRecord {
+    let (record_pubkey, _) =
+        DonorRecord::get_token_donor_record_pubkey_with_bump(CAMPAIGN_ID, mint, &env.user.pubkey());
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    DonorRecord::unpack(&account.data).unwrap()
+}
+
+async fn token_totals(env: &mut Env, mint: &Pubkey) -> DepositTotals {
+    let (totals_pubkey, _) = DepositTotals::get_token_totals_pubkey_with_bump(CAMPAIGN_ID, mint);
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(totals_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    DepositTotals::unpack(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_token_deposit_and_withdraw() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+    let destination =
+        create_token_account(&mut env, &vendor.pubkey(), &mint, &token_program, 0).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                40,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                40,
+            ),
+            // The lamports goal isn't reached, tokens only depend on the goal of their mint
+            DepositInstructions::create_withdraw_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                &destination,
+                10,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    assert_eq!(token_balance(&mut env, &source).await, 60);
+    assert_eq!(token_balance(&mut env, &vault).await, 30);
+    assert_eq!(token_balance(&mut env, &destination).await, 10);
+
+    assert_eq!(token_record(&mut env, &mint).await.amount, 40);
+    let totals = token_totals(&mut env, &mint).await;
+    assert_eq!(totals.total_amount, 40);
+    assert_eq!(totals.donors_count, 1);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(2, Custom(19)))"
+)]
+async fn test_cant_withdraw_token_before_goal() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+    let admin_pubkey = env.admin.pubkey();
+    let destination = create_token_account(&mut env, &admin_pubkey, &mint, &token_program, 0).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                50,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                40,
+            ),
+            DepositInstructions::create_withdraw_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                &destination,
+                40,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timelocked_token_proposal_withdraw() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+    let destination =
+        create_token_account(&mut env, &vendor.pubkey(), &mint, &token_program, 0).await;
+    enable_timelock(&mut env, 3600).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                40,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                40,
+            ),
+            DepositInstructions::create_token_withdraw_proposal(
+                &env.admin.pubkey(),
+                0,
+                CAMPAIGN_ID,
+                &mint,
+                10,
+                &destination,
+                "vendor invoice #1",
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_token_proposal(
+            0,
+            CAMPAIGN_ID,
+            &mint,
+            &token_program,
+            &destination,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(11))
+    );
+
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3600;
+    env.ctx.set_sysvar(&clock);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_execute_token_proposal(
+            0,
+            CAMPAIGN_ID,
+            &mint,
+            &token_program,
+            &destination,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    assert_eq!(token_balance(&mut env, &vault).await, 30);
+    assert_eq!(token_balance(&mut env, &destination).await, 10);
+}
+
+#[tokio::test]
+async fn test_reclaim_token_failed_campaign() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                50,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                40,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_reclaim_token(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            &mint,
+            &token_program,
+            &source,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    assert_eq!(token_balance(&mut env, &source).await, 100);
+    assert_eq!(token_balance(&mut env, &vault).await, 0);
+
+    assert_eq!(token_record(&mut env, &mint).await.amount, 0);
+    assert_eq!(token_totals(&mut env, &mint).await.total_amount, 0);
+}
+
+#[tokio::test]
+async fn test_token_2022_deposit_with_transfer_fee() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token_2022::id();
+    // 1% transfer fee
+    let mint = create_mint(&mut env, &token_program, Some(100)).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 1000).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                1000,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                1000,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    assert_eq!(token_balance(&mut env, &source).await, 0);
+    assert_eq!(token_balance(&mut env, &vault).await, 990);
+
+    assert_eq!(token_record(&mut env, &mint).await.amount, 990);
+    assert_eq!(token_totals(&mut env, &mint).await.total_amount, 990);
+}
+
+async fn subscribe(env: &mut Env, mint: &Pubkey, token_program: &Pubkey, source: &Pubkey) {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                mint,
+                token_program,
+                0,
+            ),
+            DepositInstructions::create_subscription(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                source,
+                mint,
+                token_program,
+                10,
+                3600,
+            ),
+            DepositInstructions::create_collect_subscription(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.user.pubkey(),
+                source,
+                mint,
+                token_program,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscription() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    subscribe(&mut env, &mint, &token_program, &source).await;
+
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3600;
+    env.ctx.set_sysvar(&clock);
+
+    // Paid by the user so the transaction differs from the first collection
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_collect_subscription(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            &env.user.pubkey(),
+            &source,
+            &mint,
+            &token_program,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    assert_eq!(token_balance(&mut env, &source).await, 80);
+    assert_eq!(token_balance(&mut env, &vault).await, 20);
+
+    assert_eq!(token_record(&mut env, &mint).await.amount, 20);
+    let totals = token_totals(&mut env, &mint).await;
+    assert_eq!(totals.total_amount, 20);
+    assert_eq!(totals.donors_count, 1);
+
+    let (subscription_pubkey, _) =
+        Subscription::get_subscription_pubkey_with_bump(CAMPAIGN_ID, &mint, &user_pubkey);
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(subscription_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let subscription = Subscription::unpack(&account.data).unwrap();
+    assert_eq!(subscription.next_collection_at, clock.unix_timestamp + 3600);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(36)))"
+)]
+async fn test_cant_collect_subscription_early() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    subscribe(&mut env, &mint, &token_program, &source).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_collect_subscription(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            &env.user.pubkey(),
+            &source,
+            &mint,
+            &token_program,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_cancel_subscription() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    subscribe(&mut env, &mint, &token_program, &source).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_cancel_subscription(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            &source,
+            &mint,
+            &token_program,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(source)
+        .await
+        .unwrap()
+        .unwrap();
+    let source_state =
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap();
+    assert_eq!(source_state.base.delegate, None.into());
+    assert_eq!(source_state.base.delegated_amount, 0);
+
+    let (subscription_pubkey, _) =
+        Subscription::get_subscription_pubkey_with_bump(CAMPAIGN_ID, &mint, &user_pubkey);
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(subscription_pubkey)
+        .await
+        .unwrap();
+    assert!(account.is_none());
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(45)))"
+)]
+async fn test_cant_subscribe_with_delegated_source() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            spl_token_2022::instruction::approve(
+                &token_program,
+                &source,
+                &Pubkey::new_unique(),
+                &env.user.pubkey(),
+                &[],
+                50,
+            )
+            .unwrap(),
+            DepositInstructions::create_subscription(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                10,
+                3600,
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(35)))"
+)]
+async fn test_cant_subscribe_with_too_long_interval() {
+    let mut env: Env = Env::new().await;
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_subscription(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            &source,
+            &mint,
+            &token_program,
+            10,
+            i64::MAX,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream() {
+    let mut env: Env = Env::new().await;
+    let beneficiary = Keypair::new();
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_stream(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &beneficiary.pubkey(),
+                sol_to_lamports(0.01),
+                clock.unix_timestamp,
+                Some(clock.unix_timestamp + 150),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    clock.unix_timestamp += 100;
+    env.ctx.set_sysvar(&clock);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_claim_stream(
+            CAMPAIGN_ID,
+            &beneficiary.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(beneficiary.pubkey())
+            .await
+            .unwrap(),
+        sol_to_lamports(1f64)
+    );
+
+    // The stream ends after 150 seconds
+    clock.unix_timestamp += 1000;
+    env.ctx.set_sysvar(&clock);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_claim_stream(
+            CAMPAIGN_ID,
+            &beneficiary.pubkey(),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(beneficiary.pubkey())
+            .await
+            .unwrap(),
+        sol_to_lamports(1.5)
+    );
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(38)))"
+)]
+async fn test_cant_claim_stream_before_start() {
+    let mut env: Env = Env::new().await;
+    let beneficiary = Keypair::new();
+    let clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_stream(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &beneficiary.pubkey(),
+                sol_to_lamports(0.01),
+                clock.unix_timestamp + 100,
+                None,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_claim_stream(
+            CAMPAIGN_ID,
+            &beneficiary.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_distribute() {
+    let mut env: Env = Env::new().await;
+    let recipients = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let shares = recipients
+        .iter()
+        .zip([5000, 3000, 2000])
+        .map(|(recipient, basis_points)| PayoutShare {
+            recipient: recipient.pubkey(),
+            basis_points,
+        })
+        .collect();
+    let recipient_pubkeys: Vec<Pubkey> = recipients.iter().map(|r| r.pubkey()).collect();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+            DepositInstructions::create_set_payout_table(&env.admin.pubkey(), CAMPAIGN_ID, shares),
+            DepositInstructions::create_distribute(CAMPAIGN_ID, &recipient_pubkeys),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    for (recipient, expected) in recipient_pubkeys.iter().zip([1.0, 0.6, 0.4]) {
+        assert_eq!(
+            env.ctx.banks_client.get_balance(*recipient).await.unwrap(),
+            sol_to_lamports(expected)
+        );
+    }
+
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(deposit_pubkey)
+            .await
+            .unwrap(),
+        953520
+    );
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(39)))"
+)]
+async fn test_cant_set_payout_table_not_summing_to_total() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_set_payout_table(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            vec![PayoutShare {
+                recipient: Pubkey::new_unique(),
+                basis_points: 9999,
+            }],
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_protocol_fee() {
+    let mut env: Env = Env::new().await;
+    let fee_recipient = Keypair::new();
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let (fee_vault_pubkey, _) = FeeVault::get_fee_vault_pubkey_with_bump();
+
+    // 2.5% fee
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_protocol_fee(
+                &env.admin.pubkey(),
+                250,
+                &fee_recipient.pubkey(),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(2f64),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(deposit_pubkey)
+            .await
+            .unwrap(),
+        sol_to_lamports(1.95) + 953520
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(fee_vault_pubkey)
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::create(
+            sol_to_lamports(0.05) + 1009200,
+            packed(&FeeVault {
+                total_collected: sol_to_lamports(0.05)
+            }),
+            id(),
+            false,
+            0,
+        )
+    );
+
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let record_account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        DonorRecord::unpack(&record_account.data).unwrap(),
+        DonorRecord {
+            donor: env.user.pubkey(),
+            amount: sol_to_lamports(1.95),
+            receipts: 0,
+            gross_amount: sol_to_lamports(2f64),
+            unburned_receipts: 0,
+        }
+    );
+
+    let campaign_account = env
+        .ctx
+        .banks_client
+        .get_account(campaign_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let campaign = Campaign::unpack(&campaign_account.data).unwrap();
+    assert_eq!(campaign.total_raised, sol_to_lamports(1.95));
+    assert_eq!(campaign.total_fees, sol_to_lamports(0.05));
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_collect_fees(
+            &fee_recipient.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(fee_recipient.pubkey())
+            .await
+            .unwrap(),
+        sol_to_lamports(0.05)
+    );
+}
+
+#[tokio::test]
+async fn test_reclaim_after_protocol_fee() {
+    let mut env: Env = Env::new().await;
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    // 2.5% fee, the gross deposits reach the goal but the net ones don't
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_protocol_fee(
+                &env.admin.pubkey(),
+                250,
+                &env.admin.pubkey(),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+            DepositInstructions::create_deposit(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.52),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_reclaim(&env.user.pubkey(), CAMPAIGN_ID),
+            DepositInstructions::create_reclaim(&env.admin.pubkey(), CAMPAIGN_ID),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(deposit_pubkey)
+            .await
+            .unwrap(),
+        953520
+    );
+
+    for donor in [env.user.pubkey(), env.admin.pubkey()] {
+        let (record_pubkey, _) =
+            DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &donor);
+        let record_account = env
+            .ctx
+            .banks_client
+            .get_account(record_pubkey)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(DonorRecord::unpack(&record_account.data).unwrap().amount, 0);
+    }
+
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(campaign_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let campaign = Campaign::unpack(&account.data).unwrap();
+    assert_eq!(campaign.total_raised, 0);
+    assert_eq!(campaign.total_fees, sol_to_lamports(0.0255));
+}
+
+#[tokio::test]
+async fn test_receipts() {
+    let mut env: Env = Env::new().await;
+    let (receipt_mint, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+    let receipt_account = get_associated_token_address_with_program_id(
+        &env.user.pubkey(),
+        &receipt_mint,
+        &spl_token::id(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_receipts(&env.admin.pubkey(), 2),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env, &receipt_account).await,
+        2 * sol_to_lamports(1f64)
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_receipt_rate(&env.admin.pubkey(), 0),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env, &receipt_account).await,
+        2 * sol_to_lamports(1f64)
+    );
+}
+
+#[tokio::test]
+async fn test_reclaim_burns_receipts() {
+    let mut env: Env = Env::new().await;
+    let (receipt_mint, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+    let receipt_account = get_associated_token_address_with_program_id(
+        &env.user.pubkey(),
+        &receipt_mint,
+        &spl_token::id(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_receipts(&env.admin.pubkey(), 2),
+            DepositInstructions::create_set_refunds_enabled(&env.admin.pubkey(), true),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+            DepositInstructions::create_claim_refund(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.2),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env, &receipt_account).await,
+        2 * sol_to_lamports(0.3)
+    );
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_reclaim(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &receipt_account).await, 0);
+
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let record_account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        DonorRecord::unpack(&record_account.data).unwrap().receipts,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_reclaim_without_receipts() {
+    let mut env: Env = Env::new().await;
+    let (receipt_mint, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+    let receipt_account = get_associated_token_address_with_program_id(
+        &env.user.pubkey(),
+        &receipt_mint,
+        &spl_token::id(),
+    );
+    let other_account = get_associated_token_address_with_program_id(
+        &env.admin.pubkey(),
+        &receipt_mint,
+        &spl_token::id(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_receipts(&env.admin.pubkey(), 2),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+            create_associated_token_account(
+                &env.admin.pubkey(),
+                &env.admin.pubkey(),
+                &receipt_mint,
+                &spl_token::id(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    // The receipts are moved away before reclaiming the deposit
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            spl_token::instruction::transfer(
+                &spl_token::id(),
+                &receipt_account,
+                &other_account,
+                &env.user.pubkey(),
+                &[],
+                sol_to_lamports(1f64),
+            )
+            .unwrap(),
+            DepositInstructions::create_reclaim(&env.user.pubkey(), CAMPAIGN_ID),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env, &other_account).await,
+        sol_to_lamports(1f64)
+    );
+
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let record_account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let record = DonorRecord::unpack(&record_account.data).unwrap();
+    assert_eq!(record.amount, 0);
+    assert_eq!(record.receipts, 0);
+    assert_eq!(record.unburned_receipts, sol_to_lamports(1f64));
+}
+
+#[tokio::test]
+async fn test_refund_without_donor_signature() {
+    let mut env: Env = Env::new().await;
+    let (receipt_mint, _) = ReceiptConfig::get_receipt_mint_pubkey_with_bump();
+    let receipt_account = get_associated_token_address_with_program_id(
+        &env.user.pubkey(),
+        &receipt_mint,
+        &spl_token::id(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_receipts(&env.admin.pubkey(), 2),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    // Only the admin signs, the receipts of the refunded share stay with the donor
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_refund(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &env.user.pubkey(),
+            sol_to_lamports(0.2),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env, &receipt_account).await,
+        sol_to_lamports(1f64)
+    );
+
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let record_account = env
+        .ctx
+        .banks_client
+        .get_account(record_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let record = DonorRecord::unpack(&record_account.data).unwrap();
+    assert_eq!(record.amount, sol_to_lamports(0.3));
+    assert_eq!(record.receipts, 2 * sol_to_lamports(0.3));
+    assert_eq!(record.unburned_receipts, 2 * sol_to_lamports(0.2));
+}
+
+#[tokio::test]
+async fn test_adopt_legacy_deposit() {
+    let mut env: Env = Env::new().await;
+    let (legacy_deposit_pubkey, _) = DepositHistoryData::get_legacy_deposit_with_bump();
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    env.ctx.set_account(
+        &legacy_deposit_pubkey,
+        &Account::create(sol_to_lamports(2f64), vec![], id(), false, 0).into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_adopt_legacy_deposit(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let deposit_account = env
+        .ctx
+        .banks_client
+        .get_account(deposit_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(deposit_account.lamports, 953520 + sol_to_lamports(2f64));
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(legacy_deposit_pubkey)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let (config_pubkey, _) = Config::get_config_pubkey_with_bump();
+    let config_account = env
+        .ctx
+        .banks_client
+        .get_account(config_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = Config::unpack(&config_account.data).unwrap();
+    assert_eq!(config.legacy_campaign_id, Some(CAMPAIGN_ID));
+}
+
+#[tokio::test]
+async fn test_migrate_history() {
+    let mut env: Env = Env::new().await;
+    let other_donor = Keypair::new();
+    let (history_pubkey, _) = DepositHistoryData::get_legacy_pda_pubkey_with_bump();
+    let (legacy_deposit_pubkey, _) = DepositHistoryData::get_legacy_deposit_with_bump();
+
+    let mut history = HashMap::new();
+    history.insert(env.user.pubkey().to_bytes(), sol_to_lamports(1f64));
+    history.insert(other_donor.pubkey().to_bytes(), sol_to_lamports(0.5));
+    let mut data = DepositHistoryData { history }.try_to_vec().unwrap();
+    data.resize(60 * 100, 0);
+    env.ctx.set_account(
+        &history_pubkey,
+        &Account::create(42650880, data, id(), false, 0).into(),
+    );
+    env.ctx.set_account(
+        &legacy_deposit_pubkey,
+        &Account::create(sol_to_lamports(1.5), vec![], id(), false, 0).into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_adopt_legacy_deposit(&env.admin.pubkey(), CAMPAIGN_ID),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.2),
+            ),
+            DepositInstructions::create_migrate_history(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &[other_donor.pubkey()],
+            ),
+            DepositInstructions::create_migrate_history(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &[env.user.pubkey()],
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    for (donor, amount) in [
+        (env.user.pubkey(), sol_to_lamports(1.2)),
+        (other_donor.pubkey(), sol_to_lamports(0.5)),
+    ] {
+        let (record_pubkey, _) =
+            DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &donor);
+        let record_account = env
+            .ctx
+            .banks_client
+            .get_account(record_pubkey)
+            .await
+            .unwrap()
+            .unwrap();
+        let record = DonorRecord::unpack(&record_account.data).unwrap();
+        assert_eq!(
+            record,
+            DonorRecord {
+                donor,
+                amount,
+                receipts: 0,
+                gross_amount: amount,
+                unburned_receipts: 0,
+            }
+        );
+    }
+
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let campaign_account = env
+        .ctx
+        .banks_client
+        .get_account(campaign_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let campaign = Campaign::unpack(&campaign_account.data).unwrap();
+    assert_eq!(campaign.donors_count, 2);
+    assert_eq!(campaign.total_raised, sol_to_lamports(1.7));
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(history_pubkey)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_account() {
+    let mut env: Env = Env::new().await;
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    // Accounts written before the header and `donors_count` were introduced
+    let legacy_campaign = [
+        CAMPAIGN_ID.to_le_bytes(),
+        sol_to_lamports(1f64).to_le_bytes(),
+        env.deadline.to_le_bytes(),
+        sol_to_lamports(0.5).to_le_bytes(),
+    ]
+    .concat();
+    env.ctx.set_account(
+        &campaign_pubkey,
+        &Account::create(1113600, legacy_campaign, id(), false, 0).into(),
+    );
+    env.ctx.set_account(
+        &deposit_pubkey,
+        &Account::create(sol_to_lamports(0.5) + 890880, vec![], id(), false, 0).into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_migrate_account(
+                &env.user.pubkey(),
+                AccountKind::Campaign {
+                    campaign_id: CAMPAIGN_ID,
+                },
+            ),
+            DepositInstructions::create_migrate_account(
+                &env.user.pubkey(),
+                AccountKind::Deposit {
+                    campaign_id: CAMPAIGN_ID,
+                },
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+        ],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let campaign = Campaign {
+        id: CAMPAIGN_ID,
+        goal_lamports: sol_to_lamports(1f64),
+        deadline: env.deadline,
+        total_raised: sol_to_lamports(1f64),
+        donors_count: 1,
+        total_fees: 0,
+        token_mints: 0,
+        allowlist_required: false,
+        open_proposals: 0,
+    };
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(campaign_pubkey)
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::create(1405920, packed(&campaign), id(), false, 0,)
+    );
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(deposit_pubkey)
+            .await
+            .unwrap()
+            .unwrap(),
+        Account::create(
+            sol_to_lamports(1f64) + 953520,
+            packed(&DepositAccount {}),
+            id(),
+            false,
+            0,
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_close_campaign() {
+    let mut env: Env = Env::new().await;
+    let recipient = Keypair::new();
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+    let (deposit_pubkey, _) = DepositHistoryData::get_deposit_with_bump(CAMPAIGN_ID);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_close(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &recipient.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(recipient.pubkey())
+            .await
+            .unwrap(),
+        1405920 + 953520
+    );
+    for pubkey in [campaign_pubkey, deposit_pubkey] {
+        assert_eq!(
+            env.ctx.banks_client.get_account(pubkey).await.unwrap(),
+            None
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_close_campaign_with_stream_and_payout_table() {
+    let mut env: Env = Env::new().await;
+    let recipient = Keypair::new();
+    let beneficiary = Keypair::new();
+    let (stream_pubkey, _) = Stream::get_stream_pubkey_with_bump(CAMPAIGN_ID);
+    let (payout_table_pubkey, _) = PayoutTable::get_payout_table_pubkey_with_bump(CAMPAIGN_ID);
+    let clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_stream(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &beneficiary.pubkey(),
+                sol_to_lamports(0.01),
+                clock.unix_timestamp,
+                None,
+            ),
+            DepositInstructions::create_set_payout_table(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                vec![PayoutShare {
+                    recipient: beneficiary.pubkey(),
+                    basis_points: 10000,
+                }],
+            ),
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &recipient.pubkey(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    for pubkey in [stream_pubkey, payout_table_pubkey] {
+        assert_eq!(
+            env.ctx.banks_client.get_account(pubkey).await.unwrap(),
+            None
+        );
+    }
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(47)))"
+)]
+async fn test_cant_close_with_active_proposal() {
+    let mut env: Env = Env::new().await;
+    let vendor = Keypair::new();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_withdraw_proposal(
+                &env.admin.pubkey(),
+                0,
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+                &vendor.pubkey(),
+                "vendor invoice #1",
+            ),
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(2, Custom(27)))"
+)]
+async fn test_cant_revive_closed_campaign() {
+    let mut env: Env = Env::new().await;
+    let (campaign_pubkey, _) = Campaign::get_campaign_pubkey_with_bump(CAMPAIGN_ID);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+            ),
+            system_instruction::transfer(&env.admin.pubkey(), &campaign_pubkey, 1231920),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(28)))"
+)]
+async fn test_cant_close_with_donations() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn reclaim_after_deadline(env: &mut Env) {
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_deposit(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+            sol_to_lamports(0.5),
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_reclaim(
+            &env.user.pubkey(),
+            CAMPAIGN_ID,
+        )],
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(0, Custom(44)))"
+)]
+async fn test_cant_close_with_donor_records() {
+    let mut env: Env = Env::new().await;
+    reclaim_after_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[DepositInstructions::create_close(
+            &env.admin.pubkey(),
+            CAMPAIGN_ID,
+            &env.admin.pubkey(),
+        )],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close_campaign_after_closing_donor_records() {
+    let mut env: Env = Env::new().await;
+    reclaim_after_deadline(&mut env).await;
+    let (record_pubkey, _) =
+        DonorRecord::get_donor_record_pubkey_with_bump(CAMPAIGN_ID, &env.user.pubkey());
+    let balance = env
+        .ctx
+        .banks_client
+        .get_balance(env.user.pubkey())
+        .await
+        .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_close_donor_records(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &[env.user.pubkey()],
+            ),
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &env.admin.pubkey(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_balance(env.user.pubkey())
+            .await
+            .unwrap(),
+        balance + 1398960
+    );
+    assert_eq!(
+        env.ctx
+            .banks_client
+            .get_account(record_pubkey)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_close_token() {
+    let mut env: Env = Env::new().await;
+    let recipient = Keypair::new();
+    let token_program = spl_token::id();
+    let mint = create_mint(&mut env, &token_program, None).await;
+    let user_pubkey = env.user.pubkey();
+    let source = create_token_account(&mut env, &user_pubkey, &mint, &token_program, 100).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_initialize_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                50,
+            ),
+            DepositInstructions::create_deposit_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &source,
+                &mint,
+                &token_program,
+                40,
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    pass_deadline(&mut env).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_reclaim_token(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                &source,
+            ),
+            DepositInstructions::create_close_token(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &mint,
+                &token_program,
+                &recipient.pubkey(),
+                &[env.user.pubkey()],
+            ),
+            DepositInstructions::create_close(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                &recipient.pubkey(),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let (totals_pubkey, _) = DepositTotals::get_token_totals_pubkey_with_bump(CAMPAIGN_ID, &mint);
+    let (record_pubkey, _) =
+        DonorRecord::get_token_donor_record_pubkey_with_bump(CAMPAIGN_ID, &mint, &user_pubkey);
+    let vault = DepositHistoryData::get_vault_pubkey(CAMPAIGN_ID, &mint, &token_program);
+    for pubkey in [totals_pubkey, record_pubkey, vault] {
+        assert_eq!(
+            env.ctx.banks_client.get_account(pubkey).await.unwrap(),
+            None
+        );
+    }
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(30)))"
+)]
+async fn test_cant_deposit_below_minimum() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_deposit_limits(
+                &env.admin.pubkey(),
+                sol_to_lamports(0.1),
+                0,
+                0,
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.05),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(3, Custom(32)))"
+)]
+async fn test_cant_exceed_donor_limit() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_deposit_limits(
+                &env.admin.pubkey(),
+                sol_to_lamports(0.1),
+                sol_to_lamports(1f64),
+                sol_to_lamports(1.5),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.5),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(3, Custom(32)))"
+)]
+async fn test_cant_exceed_donor_limit_with_fee() {
+    let mut env: Env = Env::new().await;
+    let fee_recipient = Keypair::new();
+
+    // The net deposits stay below the limit, the gross ones don't
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_set_protocol_fee(
+                &env.admin.pubkey(),
+                250,
+                &fee_recipient.pubkey(),
+            ),
+            DepositInstructions::create_set_deposit_limits(
+                &env.admin.pubkey(),
+                sol_to_lamports(0.1),
+                0,
+                sol_to_lamports(1.5),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(1f64),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.52),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_add_to_access_list(
+                &env.admin.pubkey(),
+                AccessList::Allow,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_set_allowlist_required(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                true,
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(3, Custom(33)))"
+)]
+async fn test_cant_deposit_after_allowlist_removal() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_add_to_access_list(
+                &env.admin.pubkey(),
+                AccessList::Allow,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_set_allowlist_required(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                true,
+            ),
+            DepositInstructions::create_remove_from_access_list(
+                &env.admin.pubkey(),
+                AccessList::Allow,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(1, Custom(34)))"
+)]
+async fn test_denylist() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_add_to_access_list(
+                &env.admin.pubkey(),
+                AccessList::Deny,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "called `Result::unwrap()` on an `Err` value: TransactionError(InstructionError(3, Custom(34)))"
+)]
+async fn test_denylist_with_allowlist_required() {
+    let mut env: Env = Env::new().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            DepositInstructions::create_add_to_access_list(
+                &env.admin.pubkey(),
+                AccessList::Allow,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_add_to_access_list(
+                &env.admin.pubkey(),
+                AccessList::Deny,
+                &env.user.pubkey(),
+            ),
+            DepositInstructions::create_set_allowlist_required(
+                &env.admin.pubkey(),
+                CAMPAIGN_ID,
+                true,
+            ),
+            DepositInstructions::create_deposit(
+                &env.user.pubkey(),
+                CAMPAIGN_ID,
+                sol_to_lamports(0.1),
+            ),
+        ],
+        Some(&env.admin.pubkey()),
+        &[&env.admin, &env.user],
+        env.ctx.last_blockhash,
+    );
+    env.ctx.banks_client.process_transaction(tx).await.unwrap();
+}